@@ -0,0 +1,122 @@
+# The version of the messages from bvm-bin that these functions handle.
+set -g bvm_functions_protocol_version 2
+
+function bvm_check_protocol_version
+  set -l bvm_bin_protocol_version ($argv[1] hidden protocol-version 2>/dev/null)
+  if test "$bvm_bin_protocol_version" != "$bvm_functions_protocol_version"
+    if test -z "$bvm_bin_protocol_version"
+      set bvm_bin_protocol_version unknown
+    end
+    echo "[bvm error]: The bvm shell functions (protocol version $bvm_functions_protocol_version) don't match $argv[1] (protocol version $bvm_bin_protocol_version). Run the bvm install script again and restart the shell." >&2
+    return 1
+  end
+end
+
+function bvm_handle_env_messages
+  # The messages are fish code from bvm-bin with quoted arguments that calls:
+  # * __bvm_set <key> <value>
+  # * __bvm_unset <key>
+  # * __bvm_exec <path>
+  #
+  # Usage: bvm_handle_env_messages [--local] <messages> [...args]
+  #
+  # With --local, the changes are only applied to the executed command
+  # instead of the current session.
+  set -g __bvm_is_local 0
+  if test "$argv[1]" = "--local"
+    set __bvm_is_local 1
+    set -e argv[1]
+  end
+  set -g __bvm_unset_args
+  set -g __bvm_set_args
+  set -g __bvm_exec_path
+
+  printf '%s\n' $argv[1] | source
+  set -l bvm_status $status
+  if test $bvm_status -eq 0; and test -n "$__bvm_exec_path"
+    # unlike sh, there are no sub shells, so pass the changes to the command with env
+    env $__bvm_unset_args $__bvm_set_args $__bvm_exec_path $argv[2..-1]
+    set bvm_status $status
+  end
+
+  set -e __bvm_is_local
+  set -e __bvm_unset_args
+  set -e __bvm_set_args
+  set -e __bvm_exec_path
+  return $bvm_status
+end
+
+function __bvm_set
+  if test "$__bvm_is_local" = "1"
+    set -a __bvm_set_args "$argv[1]=$argv[2]"
+  else if test "$argv[1]" = "PATH"
+    # fish stores the path as a list
+    set -gx PATH (string split : -- $argv[2])
+  else
+    set -gx $argv[1] $argv[2]
+  end
+end
+
+function __bvm_unset
+  if test "$__bvm_is_local" = "1"
+    set -a __bvm_unset_args -u $argv[1]
+  else
+    set -e -g $argv[1]
+  end
+end
+
+function __bvm_exec
+  set -g __bvm_exec_path $argv[1]
+end
+
+function bvm
+  set -l bvm_bin "$BVM_INSTALL_DIR/bin/bvm-bin"
+
+  if test "$argv[1]" = "exec-command"
+    # todo: implement the third fallback argument somehow
+    bvm_check_protocol_version $bvm_bin; or return $status
+    set -l bvm_messages (BVM_SHELL=fish $bvm_bin hidden resolve-command "$argv[2]"); or return $status
+    bvm_handle_env_messages --local (string join \n -- $bvm_messages) $argv[4..-1]
+    return $status
+
+  else if test "$argv[1]" = "exec"
+    # Format: bvm exec [name-selector] [version-selector] [command-name] [...args]
+    set -l bvm_exec_name $argv[2]
+    set -l bvm_exec_version $argv[3]
+    set -l bvm_exec_command $argv[4]
+    bvm_check_protocol_version $bvm_bin; or return $status
+    set -l bvm_has_command ($bvm_bin hidden has-command "$bvm_exec_name" "$bvm_exec_version" "$bvm_exec_command"); or return $status
+
+    if test "$bvm_has_command" = "false"
+      set bvm_exec_command $bvm_exec_name
+    end
+
+    set -l bvm_executable_path ($bvm_bin hidden get-exec-command-path "$bvm_exec_name" "$bvm_exec_version" "$bvm_exec_command"); or return $status
+
+    set -l bvm_args
+    if test "$bvm_has_command" = "false"
+      set bvm_args $argv[4..-1]
+    else
+      set bvm_args $argv[5..-1]
+    end
+
+    set -l bvm_messages (BVM_SHELL=fish $bvm_bin hidden get-exec-env-changes "$bvm_exec_name" "$bvm_exec_version"); or return $status
+    set -a bvm_messages "__bvm_exec "(string escape -- $bvm_executable_path)
+    bvm_handle_env_messages --local (string join \n -- $bvm_messages) $bvm_args
+    return $status
+  end
+
+  $bvm_bin $argv
+  set -l bvm_status $status
+
+  if contains -- "$argv[1]" install uninstall use
+    bvm_check_protocol_version $bvm_bin; or return $status
+    set -l pending_changes (BVM_SHELL=fish $bvm_bin hidden get-pending-env-changes)
+    if test -n "$pending_changes"
+      bvm_handle_env_messages (string join \n -- $pending_changes); or return $status
+      $bvm_bin hidden clear-pending-env-changes; or return $status
+    end
+  end
+
+  return $bvm_status
+end
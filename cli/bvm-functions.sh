@@ -1,66 +1,50 @@
 #!/bin/sh
 
+# The version of the messages from bvm-bin that these functions handle.
+bvm_functions_protocol_version=2
+
+bvm_check_protocol_version()
+{
+  local bvm_bin_protocol_version
+  bvm_bin_protocol_version=$("$1" hidden protocol-version 2>/dev/null)
+  if [ "$bvm_bin_protocol_version" != "$bvm_functions_protocol_version" ]
+  then
+    echo "[bvm error]: The bvm shell functions (protocol version $bvm_functions_protocol_version) don't match $1 (protocol version ${bvm_bin_protocol_version:-unknown}). Run the bvm install script again and restart the shell." >&2
+    return 1
+  fi
+}
+
 bvm_handle_env_messages()
 {
-  # * ADD\n<key>\n<value>
-  # * REMOVE\n<key>
-  # * EXEC\n<path>
-  local message_step
-  message_step=0
-  local bvm_var_name
-  local IFS
-  local lines
-  lines=$1
+  # The messages are shell code from bvm-bin with quoted arguments that calls:
+  # * __bvm_set <key> <value>
+  # * __bvm_unset <key>
+  # * __bvm_exec <path>
+  local bvm_exec_path
+  bvm_exec_path=""
+
+  eval "$1" || { return $?; }
   shift
 
-  # enable splitting a variable by IFS in zsh
-  if [ $ZSH_VERSION ]; then
-    setopt sh_word_split
+  if [ ! -z "$bvm_exec_path" ]
+  then
+    "$bvm_exec_path" "$@" || { return $?; }
   fi
+}
 
-  IFS='
-'
-  for line in $lines
-  do
-    if [ "$message_step" = "0" ]
-    then
-      if [ "$line" = "ADD" ]
-      then
-        message_step=1
-      elif [ "$line" = "REMOVE" ]
-      then
-        message_step=10
-      elif [ "$line" = "EXEC" ]
-      then
-        message_step=20
-      else
-        echo "Internal error. Unexpected output: $line"
-        return 1
-      fi
-    elif [ "$message_step" = "1" ] # adding, get var name
-    then
-      bvm_var_name="$line"
-      message_step=2
-    elif [ "$message_step" = "2" ] # adding, get var value
-    then
-      eval "$bvm_var_name"=\"\$line\"
-      export $bvm_var_name
+__bvm_set()
+{
+  export "$1=$2"
+}
 
-      message_step=0
-    elif [ "$message_step" = "10" ] # removing, get var value
-    then
-      eval "$line"=\"\"
-      export $line
-      unset "$line"
-      message_step=0
-    elif [ "$message_step" = "20" ] # exec
-    then
-      $line "$@" || { return $?; }
-    else
-      echo "Internal error. Unexpected output: $line"
-      return 1
-    fi
-  done
+__bvm_unset()
+{
+  unset "$1"
+}
+
+__bvm_exec()
+{
+  bvm_exec_path=$1
 }
 
 bvm()
@@ -71,13 +55,17 @@ bvm()
   if [ "$1" = "exec-command" ]
   then
     local bvm_exec_command
+    local bvm_messages
     bvm_exec_command=$2
     # todo: implement the third fallback argument somehow
     shift 3
 
+    bvm_check_protocol_version "$bvm_bin" || { return $?; }
+    bvm_messages=$(BVM_SHELL=sh "$bvm_bin" hidden resolve-command "$bvm_exec_command") || { return $?; }
+
     # use a sub shell to prevent exporting variables
     (
-      bvm_handle_env_messages "$($bvm_bin hidden resolve-command "$bvm_exec_command")" "$@" || { return $?; }
+      bvm_handle_env_messages "$bvm_messages" "$@" || { return $?; }
     )
 
     return $?;
@@ -89,10 +77,12 @@ bvm()
     local bvm_exec_version
     local bvm_exec_command
     local bvm_has_command
+    local bvm_messages
     bvm_exec_name=$2
     bvm_exec_version=$3
     bvm_exec_command=$4
-    bvm_has_command=$($bvm_bin hidden has-command "$bvm_exec_name" "$bvm_exec_version" "$bvm_exec_command") || { return $?; }
+    bvm_check_protocol_version "$bvm_bin" || { return $?; }
+    bvm_has_command=$("$bvm_bin" hidden has-command "$bvm_exec_name" "$bvm_exec_version" "$bvm_exec_command") || { return $?; }
 
     if [ "$bvm_has_command" = "false" ]
     then
@@ -100,7 +90,8 @@ bvm()
     fi
 
     local bvm_executable_path
-    bvm_executable_path=$($bvm_bin hidden get-exec-command-path "$bvm_exec_name" "$bvm_exec_version" "$bvm_exec_command") || { return $?; }
+    bvm_executable_path=$("$bvm_bin" hidden get-exec-command-path "$bvm_exec_name" "$bvm_exec_version" "$bvm_exec_command") || { return $?; }
+    bvm_messages=$(BVM_SHELL=sh "$bvm_bin" hidden get-exec-env-changes "$bvm_exec_name" "$bvm_exec_version") || { return $?; }
 
     if [ "$bvm_has_command" = "false" ]
     then
@@ -111,23 +102,24 @@ bvm()
 
     # use a sub shell to prevent exporting variables
     (
-      bvm_handle_env_messages "$($bvm_bin hidden get-exec-env-changes "$bvm_exec_name" "$bvm_exec_version")" || { return $?; }
+      bvm_handle_env_messages "$bvm_messages" || { return $?; }
 
-      $bvm_executable_path "$@"
+      "$bvm_executable_path" "$@"
     )
     return $?
   fi
 
-  $bvm_bin "$@"
+  "$bvm_bin" "$@"
 
   if [ "$1" = "install" ] || [ "$1" = "uninstall" ] || [ "$1" = "use" ]
   then
     local pending_changes
-    pending_changes=$($bvm_bin hidden get-pending-env-changes)
+    bvm_check_protocol_version "$bvm_bin" || { return $?; }
+    pending_changes=$(BVM_SHELL=sh "$bvm_bin" hidden get-pending-env-changes)
     if [ ! -z "$pending_changes" ]
     then
       bvm_handle_env_messages "$pending_changes" || { return $?; }
-      $bvm_bin hidden clear-pending-env-changes || { return $?; }
+      "$bvm_bin" hidden clear-pending-env-changes || { return $?; }
     fi
   fi
 }
@@ -0,0 +1,18 @@
+if test -z "$BVM_INSTALL_DIR"
+  set -g BVM_INSTALL_DIR "$HOME/.bvm"
+end
+
+source $BVM_INSTALL_DIR/bin/bvm-functions.fish
+
+# use the bin directly since we haven't set the path yet
+set -l bvm_binary_paths ($BVM_INSTALL_DIR/bin/bvm-bin hidden get-paths)
+
+if bvm_check_protocol_version $BVM_INSTALL_DIR/bin/bvm-bin
+  bvm_handle_env_messages (string join \n -- (BVM_SHELL=fish $BVM_INSTALL_DIR/bin/bvm-bin hidden get-env-vars))
+end
+
+if test -n "$bvm_binary_paths"
+  set -gx PATH (string split : -- $bvm_binary_paths) $PATH
+end
+
+set -gx PATH "$BVM_INSTALL_DIR/shims" $PATH
@@ -10,7 +10,10 @@ fi
 # use the bin directly since we haven't set the path yet
 bvm_binary_paths=$($BVM_INSTALL_DIR/bin/bvm-bin hidden get-paths)
 
-bvm_handle_env_messages "$($BVM_INSTALL_DIR/bin/bvm-bin hidden get-env-vars)"
+if bvm_check_protocol_version "$BVM_INSTALL_DIR/bin/bvm-bin"
+then
+  bvm_handle_env_messages "$(BVM_SHELL=sh "$BVM_INSTALL_DIR/bin/bvm-bin" hidden get-env-vars)"
+fi
 
 if [ ! -z "$bvm_binary_paths" ]
 then
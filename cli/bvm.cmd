@@ -4,6 +4,10 @@ SET bvm_exit_code=0
 SET bvm_bin=%~dp0bvm-bin
 REM Escape spaces in the path
 SET bvm_bin=%bvm_bin: =^ %
+SET bvm_protocol_version=2
+
+REM The hidden commands output messages for cmd when BVM_SHELL is not set
+SET BVM_SHELL=
 
 IF "%1" == "exec-command" GOTO bvmexeccommand
 IF "%1" == "exec" GOTO bvmexec
@@ -18,6 +22,12 @@ GOTO end
 
 :checkenvchanges
 
+CALL :checkprotocolversion
+IF ERRORLEVEL 1 (
+  SET bvm_exit_code=1
+  GOTO end
+)
+
 REM Check if any changes to the environment are necessary...
 SET bvm_had_env_changes=
 FOR /F "delims=" %%F in ('%bvm_bin% hidden get-pending-env-changes') do (
@@ -33,6 +43,12 @@ GOTO end
 
 :bvmexeccommand
 
+CALL :checkprotocolversion
+IF ERRORLEVEL 1 (
+  SET bvm_exit_code=1
+  GOTO end
+)
+
 REM Get the args after the first three by escaping the quotes,
 REM surrounding them in quotes, removing the args from the string,
 REM then removing the surrounding quotes and unescaping the quotes
@@ -45,9 +61,9 @@ FOR /F "tokens=* USEBACKQ" %%F IN (`%bvm_bin% hidden slice-args 3 true "%bvm_exe
 SET bvm_exec_args=%bvm_exec_args:""="%
 SET bvm_exec_args=%bvm_exec_args:~1,-1%
 
-REM Enable delayed expansion from here (so that the args above aren't affected)
-REM This is necessary for executing the results of resolve-command
-SETLOCAL EnableDelayedExpansion
+REM Prevent changing the environment for this command. Delayed expansion is
+REM disabled so that any ! and ^ in the environment variable values are kept
+SETLOCAL DisableDelayedExpansion
 
 SET bvm_exec_command=%2
 SET bvm_exec_exe_path=
@@ -59,23 +75,20 @@ if "%USERNAME%" == "" (
   GOTO bvmexeccommandfinish
 )
 
-SET bvm_was_last_exec=
-FOR /F "delims=" %%F in ('%bvm_bin% hidden resolve-command %bvm_exec_command%') do (
-  IF !bvm_was_last_exec! == "1" (
-    SET bvm_was_last_exec=
-    SET bvm_exec_exe_path="%%F"
-    GOTO bvmexeccommandfinish
-  )
-  IF "%%F" == "EXEC" (
-    SET bvm_was_last_exec="1"
+REM Each line is either a SET command to run or EXEC followed by the executable's path
+FOR /F "tokens=1* delims= " %%A in ('%bvm_bin% hidden resolve-command %bvm_exec_command%') do (
+  IF "%%A" == "EXEC" (
+    SET bvm_exec_exe_path="%%B"
   ) ELSE (
-    %%F
+    %%A %%B
   )
 )
 
-REM being here indicates the command above failed
-SET bvm_exit_code=1
-GOTO end
+REM not having a path indicates the command above failed
+IF [%bvm_exec_exe_path%] == [] (
+  SET bvm_exit_code=1
+  GOTO end
+)
 
 :bvmexeccommandfinish
 
@@ -85,8 +98,14 @@ SET bvm_exit_code=%ERRORLEVEL%
 GOTO end
 
 :bvmexec
-REM Prevent changing the environment for this command
-SETLOCAL
+REM Prevent changing the environment for this command and keep any ! and ^ in the values
+SETLOCAL DisableDelayedExpansion
+
+CALL :checkprotocolversion
+IF ERRORLEVEL 1 (
+  SET bvm_exit_code=1
+  GOTO end
+)
 
 REM Format: bvm exec [name-selector] [version-selector] <command-name> [...args]
 REM Note: Version may include a caret "^1.1.2" so it seems it needs to be referenced directly here
@@ -140,12 +159,25 @@ REM Execute
 SET bvm_exit_code=%ERRORLEVEL%
 GOTO end
 
+:checkprotocolversion
+SET bvm_bin_protocol_version=
+FOR /F "tokens=*" %%F IN ('%bvm_bin% hidden protocol-version') DO (
+  SET bvm_bin_protocol_version=%%F
+)
+IF NOT "%bvm_bin_protocol_version%" == "%bvm_protocol_version%" (
+  ECHO [bvm error]: The bvm shell script is out of date ^(protocol version %bvm_protocol_version%, but bvm-bin is %bvm_bin_protocol_version%^). Try running the bvm install script again. 1>&2
+  EXIT /B 1
+)
+EXIT /B 0
+
 :end
 REM Unset any globally set variables (most of them run under SETLOCAL so no need to unset)
 SET bvm_had_env_changes=
 SET bvm_powershell_path=
 SET bvm_bin=
 SET bvm_exec_args=
+SET bvm_protocol_version=
+SET bvm_bin_protocol_version=
 
 REM How to clear this before exit?
 IF %bvm_exit_code% GTR 0 (
@@ -2,6 +2,41 @@
 
 $bvm_bin = Join-Path -Path $PSScriptRoot -ChildPath "bvm-bin"
 
+$bvm_protocol_version = "2"
+
+function check_protocol_version {
+  $version = ((. $bvm_bin hidden protocol-version) | Out-String).trim()
+  if ($version -ne $bvm_protocol_version) {
+    throw "The bvm shell script is out of date (protocol version $bvm_protocol_version, but bvm-bin is $version). Try running the bvm install script again."
+  }
+}
+
+# runs a hidden command with the messages formatted for powershell
+function get_bvm_messages {
+  $previous_bvm_shell = $env:BVM_SHELL
+  $env:BVM_SHELL = "powershell"
+  try {
+    return ((. $bvm_bin @args) | Out-String)
+  } finally {
+    $env:BVM_SHELL = $previous_bvm_shell
+  }
+}
+
+function __bvm_set {
+  param([string]$name, [string]$value)
+  [Environment]::SetEnvironmentVariable($name, $value)
+}
+
+function __bvm_unset {
+  param([string]$name)
+  [Environment]::SetEnvironmentVariable($name, $null)
+}
+
+function __bvm_exec {
+  param([string]$path)
+  $script:bvm_exec_path = $path
+}
+
 function bvm_handle_env_messages {
   param(
     [string]
@@ -10,39 +45,22 @@ function bvm_handle_env_messages {
     $exec_args
   )
 
-  $items = $messages_text -split [Environment]::NewLine
-  foreach ($item in $items) {
-    if ([string]::IsNullOrWhitespace($item)) {
-        # ignore
-    } elseif ($last_was_exec -eq 1) {
-      # this item will be the binary to execute
-      . $item @exec_args # splat the arguments
-      if ($lastexitcode -ne 0) { exit $lastexitcode }
-    } elseif ($item -match "^SET ([^\s]+)=(.+)$") {
-      # adding env var
-      $name = $Matches.1
-      Set-Item "env:$name" $Matches.2
-    } elseif ($item -match "^SET ([^\s]+)=$") {
-      # removing env var
-      $name = $Matches.1
-      Remove-Item "env:$name" -ErrorAction SilentlyContinue
-    } elseif ($item.trim() -eq "EXEC") {
-      # set that the next item should be the binary that's executed
-      $last_was_exec=1
-    } else {
-      throw "Internal error in bvm. Unhandled line: $item"
-    }
+  $script:bvm_exec_path = $null
+  if (-not [string]::IsNullOrWhiteSpace($messages_text)) {
+    Invoke-Expression $messages_text
+  }
+  if ($script:bvm_exec_path -ne $null) {
+    . $script:bvm_exec_path @exec_args # splat the arguments
+    if ($lastexitcode -ne 0) { exit $lastexitcode }
   }
 }
 
 function has_env_changes {
     param([string]$messages_text)
-    $messages_text = $messages_text.trim()
-    $new_line = [Environment]::NewLine
-    if (($messages_text -eq $null) -or ($messages_text.Length -eq 0) -or ($messages_text.StartsWith("EXEC$new_line"))) {
-        return 0
-    } else {
+    if ($messages_text -match "__bvm_(set|unset) ") {
         return 1
+    } else {
+        return 0
     }
 }
 
@@ -89,6 +107,10 @@ function process_args {
 
 $args=(process_args @args)
 
+if (($args[0] -eq "exec-command") -or ($args[0] -eq "exec") -or ($args[0] -eq "install") -or ($args[0] -eq "uninstall") -or ($args[0] -eq "use")) {
+  check_protocol_version
+}
+
 if ($args[0] -eq "exec-command") {
   # Format: bvm exec-command [command-name] [...args]
   $command_name = $args[1]
@@ -98,7 +120,7 @@ if ($args[0] -eq "exec-command") {
   if (($env:USERNAME -eq "") -or ($env:USERNAME -eq $null)) {
     . $fallback_path @exec_args # splat the arguments
   } else {
-    $env_messages=((. $bvm_bin hidden resolve-command $command_name) | Out-String)
+    $env_messages=(get_bvm_messages hidden resolve-command $command_name)
     $should_snapshot_env=(has_env_changes $env_messages)
     if ($should_snapshot_env -eq 1) { $env_snapshot=(snapshot_env) }
     try {
@@ -126,7 +148,7 @@ if ($args[0] -eq "exec-command") {
   $executable_path=((. $bvm_bin hidden get-exec-command-path $exec_name $exec_version $exec_command) | Out-String).trim()
   if ($lastexitcode -ne 0) { exit $lastexitcode }
 
-  $env_messages=((. $bvm_bin hidden get-exec-env-changes $exec_name $exec_version) | Out-String)
+  $env_messages=(get_bvm_messages hidden get-exec-env-changes $exec_name $exec_version)
   $should_snapshot_env=(has_env_changes $env_messages)
   if ($should_snapshot_env -eq 1) { $env_snapshot=(snapshot_env) }
   try {
@@ -141,7 +163,7 @@ if ($args[0] -eq "exec-command") {
   . $bvm_bin @args # splat
 
   if (($args[0] -eq "install") -or ($args[0] -eq "uninstall") -or ($args[0] -eq "use")) {
-    bvm_handle_env_messages ((. $bvm_bin hidden get-pending-env-changes) | Out-String)
+    bvm_handle_env_messages (get_bvm_messages hidden get-pending-env-changes)
     if ($lastexitcode -ne 0) { exit $lastexitcode }
     . $bvm_bin hidden clear-pending-env-changes
     if ($lastexitcode -ne 0) { exit $lastexitcode }
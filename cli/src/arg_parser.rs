@@ -1,11 +1,14 @@
+use std::path::PathBuf;
 use url::Url;
 
 use dprint_cli_core::types::ErrBox;
 
 use crate::environment::Environment;
+use crate::plugins::{PlatformInfoCommand, PluginPlatform};
+use crate::settings::SettingKey;
 use crate::utils::{get_url_from_directory, parse_checksum_url, ChecksumUrl};
 
-use super::types::{CommandName, NameSelector, PathOrVersionSelector, Version, VersionSelector};
+use super::types::{BinaryName, CommandName, NameSelector, PathOrVersionSelector, Version, VersionSelector};
 
 pub struct CliArgs {
   pub sub_command: SubCommand,
@@ -17,13 +20,25 @@ pub enum SubCommand {
   List,
   Install(InstallCommand),
   InstallUrl(InstallUrlCommand),
+  InstallArchive(InstallArchiveCommand),
   Uninstall(UninstallCommand),
+  Reinstall(ReinstallCommand),
+  Link(LinkCommand),
+  Unlink(UnlinkCommand),
   Registry(RegistrySubCommand),
+  Plugin(PluginSubCommand),
+  Search(SearchCommand),
+  Info(InfoCommand),
+  Config(ConfigSubCommand),
   Add(AddCommand),
   Version,
   Init,
   ClearUrlCache,
   RecreateShims,
+  Hook(HookCommand),
+  Completions(CompletionsCommand),
+  Env(EnvCommand),
+  Run(RunCommand),
   Hidden(HiddenSubCommand),
   Help(String),
 }
@@ -36,12 +51,24 @@ pub struct UseBinaryCommand {
 pub struct InstallCommand {
   pub use_command: bool,
   pub force: bool,
+  pub ignore_scripts: bool,
 }
 
 pub struct InstallUrlCommand {
   pub url_or_name: UrlOrName,
   pub use_command: bool,
   pub force: bool,
+  pub ignore_scripts: bool,
+}
+
+pub struct InstallArchiveCommand {
+  pub url: ChecksumUrl,
+  pub binary_name: BinaryName,
+  pub version: Version,
+  pub commands: Vec<PlatformInfoCommand>,
+  pub use_command: bool,
+  pub force: bool,
+  pub ignore_scripts: bool,
 }
 
 pub enum UrlOrName {
@@ -57,12 +84,33 @@ pub struct InstallName {
 pub struct UninstallCommand {
   pub name_selector: NameSelector,
   pub version: Version,
+  pub skip_scripts: bool,
+}
+
+pub struct ReinstallCommand {
+  pub name_selector: NameSelector,
+  pub version: Version,
+  pub ignore_scripts: bool,
+}
+
+pub struct LinkCommand {
+  pub dir_path: PathBuf,
+  pub binary_name: BinaryName,
+  pub version: Version,
+  pub commands: Vec<PlatformInfoCommand>,
+}
+
+pub struct UnlinkCommand {
+  pub name_selector: NameSelector,
+  pub version: Version,
 }
 
 pub enum RegistrySubCommand {
   Add(RegistryAddCommand),
   Remove(RegistryRemoveCommand),
   List,
+  Create(RegistryCreateCommand),
+  AddVersion(RegistryAddVersionCommand),
 }
 
 pub struct RegistryAddCommand {
@@ -73,8 +121,104 @@ pub struct RegistryRemoveCommand {
   pub url: String,
 }
 
+pub struct RegistryCreateCommand {
+  pub dir_path: PathBuf,
+}
+
+pub struct RegistryAddVersionCommand {
+  pub file_path: PathBuf,
+  pub url: ChecksumUrl,
+  /// The url the binary manifest file is published at when it differs from the provided url.
+  pub published_url: Option<String>,
+}
+
+pub enum PluginSubCommand {
+  New(PluginNewCommand),
+  Contents(PluginContentsCommand),
+  Checksum(PluginChecksumCommand),
+  Validate(PluginValidateCommand),
+}
+
+pub struct PluginNewCommand {
+  pub binary_name: BinaryName,
+  pub version: Version,
+  pub description: String,
+  pub platform_urls: Vec<(PluginPlatform, ChecksumUrl)>,
+}
+
+pub struct PluginContentsCommand {
+  pub url: ChecksumUrl,
+}
+
+pub struct PluginChecksumCommand {
+  pub file_path: PathBuf,
+}
+
+pub struct PluginValidateCommand {
+  pub file_path: PathBuf,
+}
+
+pub struct SearchCommand {
+  pub term: String,
+}
+
+pub struct InfoCommand {
+  pub name_selector: NameSelector,
+}
+
+pub enum ConfigSubCommand {
+  Get(ConfigGetCommand),
+  Set(ConfigSetCommand),
+  List,
+}
+
+pub struct ConfigGetCommand {
+  pub key: SettingKey,
+}
+
+pub struct ConfigSetCommand {
+  pub key: SettingKey,
+  pub value: String,
+}
+
 pub struct AddCommand {
   pub url_or_name: UrlOrName,
+  pub ignore_scripts: bool,
+}
+
+pub struct HookCommand {
+  pub shell: Shell,
+}
+
+pub struct CompletionsCommand {
+  pub shell: Shell,
+}
+
+pub struct RunCommand {
+  pub command_name: CommandName,
+  pub args: Vec<String>,
+}
+
+pub struct EnvCommand {
+  /// Output the environment of the configuration file's binaries instead of the global versions.
+  pub config: bool,
+  pub format: EnvFormat,
+}
+
+pub enum EnvFormat {
+  Sh,
+  Fish,
+  Powershell,
+  Github,
+  Dotenv,
+  Json,
+}
+
+pub enum Shell {
+  Bash,
+  Zsh,
+  Fish,
+  Powershell,
 }
 
 #[cfg(target_os = "windows")]
@@ -93,6 +237,9 @@ pub enum HiddenSubCommand {
   ClearPendingEnvChanges,
   GetPaths,
   GetEnvVars,
+  HookEnv,
+  ProtocolVersion,
+  Complete(HiddenCompleteCommand),
   #[cfg(not(target_os = "windows"))]
   UnixInstall,
   #[cfg(target_os = "windows")]
@@ -118,6 +265,13 @@ pub struct HiddenGetExecCommandPathCommand {
   pub command_name: CommandName,
 }
 
+pub struct HiddenCompleteCommand {
+  /// Index in `words` of the word being completed.
+  pub index: usize,
+  /// The words after `bvm`.
+  pub words: Vec<String>,
+}
+
 pub struct HiddenHasCommandCommand {
   pub name_selector: NameSelector,
   pub version_selector: PathOrVersionSelector,
@@ -127,6 +281,17 @@ pub struct HiddenHasCommandCommand {
 pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<CliArgs, ErrBox> {
   let base_url = get_url_from_directory(environment.cwd());
   // need to do this to bypass clap
+  if args.get(1).map(|s| s.as_str()) == Some("run") {
+    // let clap handle the help flags and the missing command error
+    if let Some(command_name) = args.get(2).filter(|arg| !arg.starts_with('-')) {
+      return Ok(CliArgs {
+        sub_command: SubCommand::Run(RunCommand {
+          command_name: CommandName::from_string(command_name.to_string()),
+          args: args[3..].to_vec(),
+        }),
+      });
+    }
+  }
   if args.get(1).map(|s| s.as_str()) == Some("hidden") {
     match args.get(2).map(|s| s.as_str()) {
       Some("has-command") => {
@@ -151,6 +316,17 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
           })),
         })
       }
+      Some("complete") => {
+        return Ok(CliArgs {
+          sub_command: SubCommand::Hidden(HiddenSubCommand::Complete(HiddenCompleteCommand {
+            index: args
+              .get(3)
+              .and_then(|value| value.parse::<usize>().ok())
+              .expect("Expected to have a word index argument."),
+            words: args.get(4..).map(|words| words.to_vec()).unwrap_or_default(),
+          })),
+        })
+      }
       #[cfg(windows)]
       Some("slice-args") => {
         return Ok(CliArgs {
@@ -192,6 +368,10 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
       SubCommand::Hidden(HiddenSubCommand::GetPaths)
     } else if matches.is_present("get-env-vars") {
       SubCommand::Hidden(HiddenSubCommand::GetEnvVars)
+    } else if matches.is_present("hook-env") {
+      SubCommand::Hidden(HiddenSubCommand::HookEnv)
+    } else if matches.is_present("protocol-version") {
+      SubCommand::Hidden(HiddenSubCommand::ProtocolVersion)
     } else if matches.is_present("get-exec-env-changes") {
       let matches = matches.subcommand_matches("get-exec-env-changes").unwrap();
       SubCommand::Hidden(HiddenSubCommand::GetExecEnvChanges(HiddenExecEnvChangesCommand {
@@ -227,7 +407,27 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
     let install_matches = matches.subcommand_matches("install").unwrap();
     let use_command = install_matches.is_present("use");
     let force = install_matches.is_present("force");
-    if let Some(url_or_name) = install_matches.value_of("url_or_name").map(String::from) {
+    let ignore_scripts = install_matches.is_present("ignore_scripts");
+    if let Some(archive) = install_matches.value_of("archive") {
+      let name_selector = parse_name_selector(install_matches.value_of("archive_name").map(String::from).unwrap());
+      let binary_name = match name_selector.owner {
+        Some(owner) => BinaryName::new(owner, name_selector.name),
+        None => return err!("Please provide the binary name in the format <owner>/<name>."),
+      };
+      let mut commands = Vec::new();
+      for command in install_matches.values_of("archive_command").unwrap() {
+        commands.push(parse_command_arg(command)?);
+      }
+      SubCommand::InstallArchive(InstallArchiveCommand {
+        url: parse_checksum_url(archive, &base_url)?,
+        binary_name,
+        version: Version::parse(install_matches.value_of("archive_version").unwrap())?,
+        commands,
+        use_command,
+        force,
+        ignore_scripts,
+      })
+    } else if let Some(url_or_name) = install_matches.value_of("url_or_name").map(String::from) {
       let version = install_matches.value_of("version").map(String::from);
       if version.is_some() || !url_or_name.to_lowercase().ends_with(".json") {
         let name_selector = parse_name_selector(url_or_name);
@@ -242,16 +442,22 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
           }),
           use_command,
           force,
+          ignore_scripts,
         })
       } else {
         SubCommand::InstallUrl(InstallUrlCommand {
           url_or_name: UrlOrName::Url(parse_checksum_url(&url_or_name, &base_url)?),
           use_command,
           force,
+          ignore_scripts,
         })
       }
     } else {
-      SubCommand::Install(InstallCommand { use_command, force })
+      SubCommand::Install(InstallCommand {
+        use_command,
+        force,
+        ignore_scripts,
+      })
     }
   } else if matches.is_present("use") {
     let use_matches = matches.subcommand_matches("use").unwrap();
@@ -275,6 +481,38 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
     SubCommand::Uninstall(UninstallCommand {
       name_selector,
       version: Version::parse(&uninstall_matches.value_of("version").map(String::from).unwrap())?,
+      skip_scripts: uninstall_matches.is_present("skip_scripts"),
+    })
+  } else if matches.is_present("reinstall") {
+    let reinstall_matches = matches.subcommand_matches("reinstall").unwrap();
+    let name_selector = parse_name_selector(reinstall_matches.value_of("binary_name").map(String::from).unwrap());
+    SubCommand::Reinstall(ReinstallCommand {
+      name_selector,
+      version: Version::parse(&reinstall_matches.value_of("version").map(String::from).unwrap())?,
+      ignore_scripts: reinstall_matches.is_present("ignore_scripts"),
+    })
+  } else if matches.is_present("link") {
+    let link_matches = matches.subcommand_matches("link").unwrap();
+    let name_selector = parse_name_selector(link_matches.value_of("binary_name").map(String::from).unwrap());
+    let binary_name = match name_selector.owner {
+      Some(owner) => BinaryName::new(owner, name_selector.name),
+      None => return err!("Please provide the binary name in the format <owner>/<name>."),
+    };
+    let mut commands = Vec::new();
+    for command in link_matches.values_of("command").unwrap() {
+      commands.push(parse_command_arg(command)?);
+    }
+    SubCommand::Link(LinkCommand {
+      dir_path: PathBuf::from(link_matches.value_of("dir").unwrap()),
+      binary_name,
+      version: Version::parse(link_matches.value_of("version").unwrap())?,
+      commands,
+    })
+  } else if matches.is_present("unlink") {
+    let unlink_matches = matches.subcommand_matches("unlink").unwrap();
+    SubCommand::Unlink(UnlinkCommand {
+      name_selector: parse_name_selector(unlink_matches.value_of("binary_name").map(String::from).unwrap()),
+      version: Version::parse(unlink_matches.value_of("version").unwrap())?,
     })
   } else if matches.is_present("list") {
     SubCommand::List
@@ -284,6 +522,46 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
     SubCommand::ClearUrlCache
   } else if matches.is_present("recreate-shims") {
     SubCommand::RecreateShims
+  } else if matches.is_present("hook") {
+    let matches = matches.subcommand_matches("hook").unwrap();
+    SubCommand::Hook(HookCommand {
+      shell: parse_shell(matches.value_of("shell").unwrap()),
+    })
+  } else if matches.is_present("completions") {
+    let matches = matches.subcommand_matches("completions").unwrap();
+    SubCommand::Completions(CompletionsCommand {
+      shell: parse_shell(matches.value_of("shell").unwrap()),
+    })
+  } else if matches.is_present("run") {
+    let matches = matches.subcommand_matches("run").unwrap();
+    SubCommand::Run(RunCommand {
+      command_name: CommandName::from_string(matches.value_of("command").map(String::from).unwrap()),
+      args: matches
+        .values_of("args")
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default(),
+    })
+  } else if matches.is_present("env") {
+    let matches = matches.subcommand_matches("env").unwrap();
+    SubCommand::Env(EnvCommand {
+      config: matches.is_present("config"),
+      format: match matches.value_of("format") {
+        Some("sh") => EnvFormat::Sh,
+        Some("fish") => EnvFormat::Fish,
+        Some("powershell") => EnvFormat::Powershell,
+        Some("github") => EnvFormat::Github,
+        Some("dotenv") => EnvFormat::Dotenv,
+        Some("json") => EnvFormat::Json,
+        Some(_) => unreachable!(),
+        None => {
+          if cfg!(target_os = "windows") {
+            EnvFormat::Powershell
+          } else {
+            EnvFormat::Sh
+          }
+        }
+      },
+    })
   } else if matches.is_present("registry") {
     let registry_sub_command = matches.subcommand_matches("registry").unwrap();
     match registry_sub_command.subcommand() {
@@ -294,12 +572,82 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
         url: matches.value_of("url").map(String::from).unwrap(),
       })),
       ("list", _) => SubCommand::Registry(RegistrySubCommand::List),
+      ("create", Some(matches)) => SubCommand::Registry(RegistrySubCommand::Create(RegistryCreateCommand {
+        dir_path: PathBuf::from(matches.value_of("dir").unwrap()),
+      })),
+      ("add-version", Some(matches)) => {
+        SubCommand::Registry(RegistrySubCommand::AddVersion(RegistryAddVersionCommand {
+          file_path: PathBuf::from(matches.value_of("file").unwrap()),
+          url: parse_checksum_url(matches.value_of("url").unwrap(), &base_url)?,
+          published_url: matches.value_of("published-url").map(String::from),
+        }))
+      }
+      _ => unreachable!(),
+    }
+  } else if matches.is_present("plugin") {
+    let plugin_sub_command = matches.subcommand_matches("plugin").unwrap();
+    match plugin_sub_command.subcommand() {
+      ("new", Some(matches)) => {
+        let name_selector = parse_name_selector(matches.value_of("binary_name").map(String::from).unwrap());
+        let binary_name = match name_selector.owner {
+          Some(owner) => BinaryName::new(owner, name_selector.name),
+          None => return err!("Please provide the binary name in the format <owner>/<name>."),
+        };
+        let mut platform_urls = Vec::new();
+        for platform in PluginPlatform::all() {
+          if let Some(url) = matches.value_of(platform.as_str()) {
+            platform_urls.push((platform, parse_checksum_url(url, &base_url)?));
+          }
+        }
+        if platform_urls.is_empty() {
+          return err!("Please provide the url of at least one platform's archive (ex. --linux-x86_64 <url>).");
+        }
+        SubCommand::Plugin(PluginSubCommand::New(PluginNewCommand {
+          binary_name,
+          version: Version::parse(matches.value_of("version").unwrap())?,
+          description: matches.value_of("description").map(String::from).unwrap_or_default(),
+          platform_urls,
+        }))
+      }
+      ("contents", Some(matches)) => SubCommand::Plugin(PluginSubCommand::Contents(PluginContentsCommand {
+        url: parse_checksum_url(matches.value_of("url").unwrap(), &base_url)?,
+      })),
+      ("checksum", Some(matches)) => SubCommand::Plugin(PluginSubCommand::Checksum(PluginChecksumCommand {
+        file_path: PathBuf::from(matches.value_of("file").unwrap()),
+      })),
+      ("validate", Some(matches)) => SubCommand::Plugin(PluginSubCommand::Validate(PluginValidateCommand {
+        file_path: PathBuf::from(matches.value_of("file").unwrap()),
+      })),
+      _ => unreachable!(),
+    }
+  } else if matches.is_present("search") {
+    let matches = matches.subcommand_matches("search").unwrap();
+    SubCommand::Search(SearchCommand {
+      term: matches.value_of("term").map(String::from).unwrap(),
+    })
+  } else if matches.is_present("info") {
+    let matches = matches.subcommand_matches("info").unwrap();
+    SubCommand::Info(InfoCommand {
+      name_selector: parse_name_selector(matches.value_of("binary_name").map(String::from).unwrap()),
+    })
+  } else if matches.is_present("config") {
+    let config_sub_command = matches.subcommand_matches("config").unwrap();
+    match config_sub_command.subcommand() {
+      ("get", Some(matches)) => SubCommand::Config(ConfigSubCommand::Get(ConfigGetCommand {
+        key: SettingKey::parse(matches.value_of("key").unwrap())?,
+      })),
+      ("set", Some(matches)) => SubCommand::Config(ConfigSubCommand::Set(ConfigSetCommand {
+        key: SettingKey::parse(matches.value_of("key").unwrap())?,
+        value: matches.value_of("value").map(String::from).unwrap(),
+      })),
+      ("list", _) => SubCommand::Config(ConfigSubCommand::List),
       _ => unreachable!(),
     }
   } else if matches.is_present("add") {
     let matches = matches.subcommand_matches("add").unwrap();
     let url_or_name = matches.value_of("url_or_name").map(String::from).unwrap();
     let version = matches.value_of("version").map(String::from);
+    let ignore_scripts = matches.is_present("ignore_scripts");
     if version.is_some() || Url::parse(&url_or_name).is_err() {
       let name_selector = parse_name_selector(url_or_name);
       SubCommand::Add(AddCommand {
@@ -311,10 +659,12 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
             None
           },
         }),
+        ignore_scripts,
       })
     } else {
       SubCommand::Add(AddCommand {
         url_or_name: UrlOrName::Url(parse_checksum_url(&url_or_name, &base_url)?),
+        ignore_scripts,
       })
     }
   } else {
@@ -328,7 +678,44 @@ pub fn parse_args(environment: &impl Environment, args: Vec<String>) -> Result<C
   Ok(CliArgs { sub_command })
 }
 
-fn parse_name_selector(text: String) -> NameSelector {
+fn parse_command_arg(text: &str) -> Result<PlatformInfoCommand, ErrBox> {
+  match text.find('=') {
+    Some(index) if index > 0 && index < text.len() - 1 => Ok(PlatformInfoCommand {
+      name: CommandName::from_string(text[..index].to_string()),
+      path: text[index + 1..].to_string(),
+    }),
+    _ => err!(
+      "Please provide the command in the format <name>=<path> (ex. --command deno=bin/deno), but found '{}'.",
+      text
+    ),
+  }
+}
+
+fn parse_shell(text: &str) -> Shell {
+  match text {
+    "bash" => Shell::Bash,
+    "zsh" => Shell::Zsh,
+    "fish" => Shell::Fish,
+    "powershell" => Shell::Powershell,
+    _ => unreachable!(),
+  }
+}
+
+/// Generates the clap completions script for the shell, which only completes the
+/// subcommands and options.
+pub fn get_static_completions(shell: &Shell) -> String {
+  let clap_shell = match shell {
+    Shell::Bash => clap::Shell::Bash,
+    Shell::Zsh => clap::Shell::Zsh,
+    Shell::Fish => clap::Shell::Fish,
+    Shell::Powershell => clap::Shell::PowerShell,
+  };
+  let mut bytes = Vec::new();
+  create_cli_parser().gen_completions_to("bvm", clap_shell, &mut bytes);
+  String::from_utf8(bytes).unwrap()
+}
+
+pub fn parse_name_selector(text: String) -> NameSelector {
   let index = text.find('/');
   if let Some(index) = index {
     let owner_name = text[0..index].to_string();
@@ -390,6 +777,40 @@ ARGS:
                         .help("The version of the binary to install.")
                         .takes_value(true)
                 )
+                .arg(
+                    Arg::with_name("archive")
+                        .help("The url or file path of an archive to install without a binary manifest file.")
+                        .long("archive")
+                        .takes_value(true)
+                        .conflicts_with_all(&["url_or_name", "version"])
+                        .requires_all(&["archive_name", "archive_version", "archive_command"]),
+                )
+                .arg(
+                    Arg::with_name("archive_name")
+                        .help("The binary name of the archive in the format <owner>/<name>.")
+                        .long("name")
+                        .value_name("owner/name")
+                        .takes_value(true)
+                        .requires("archive"),
+                )
+                .arg(
+                    Arg::with_name("archive_version")
+                        .help("The version of the archive's binary.")
+                        .long("version")
+                        .value_name("version")
+                        .takes_value(true)
+                        .requires("archive"),
+                )
+                .arg(
+                    Arg::with_name("archive_command")
+                        .help("A command in the archive in the format <name>=<path>. Can be provided multiple times.")
+                        .long("command")
+                        .value_name("name=path")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .requires("archive"),
+                )
                 .arg(
                     Arg::with_name("use")
                         .help("Use the installed binary/binaries on the path.")
@@ -401,6 +822,12 @@ ARGS:
                         .help("Reinstall the binary/binaries if it is already installed.")
                         .long("force")
                         .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("ignore_scripts")
+                        .help("Install without running the onPreInstall and onPostInstall scripts.")
+                        .long("ignore-scripts")
+                        .takes_value(false),
                 ),
         )
         .subcommand(
@@ -417,6 +844,82 @@ ARGS:
                         .help("The version of the binary to uninstall.")
                         .takes_value(true)
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("skip_scripts")
+                        .help("Uninstall without running the onPreUninstall and onPostUninstall scripts.")
+                        .long("skip-scripts")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("reinstall")
+                .about("Reinstalls the specified binary version from the binary manifest file or archive it was installed from.")
+                .arg(
+                    Arg::with_name("binary_name")
+                        .help("The binary name.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("version")
+                        .help("The version of the binary to reinstall.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("ignore_scripts")
+                        .help("Reinstall without running the onPreInstall and onPostInstall scripts.")
+                        .long("ignore-scripts")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("link")
+                .about("Registers a local directory, such as a development build, as an installed version of a binary.")
+                .arg(
+                    Arg::with_name("dir")
+                        .help("The directory containing the binary's files.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("binary_name")
+                        .help("The binary name in the format <owner>/<name>.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("version")
+                        .help("The version to register the directory as.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("command")
+                        .help("A command in the directory in the format <name>=<path>. Can be provided multiple times.")
+                        .long("command")
+                        .value_name("name=path")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unlink")
+                .about("Removes a binary version registered with `bvm link` without deleting its directory.")
+                .arg(
+                    Arg::with_name("binary_name")
+                        .help("The binary name.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("version")
+                        .help("The version of the binary to unlink.")
+                        .takes_value(true)
+                        .required(true),
                 ),
         )
         .subcommand(
@@ -449,9 +952,71 @@ ARGS:
                         .help("The version to add if providing a name.")
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("ignore_scripts")
+                        .help("Install without running the binary's onPreInstall and onPostInstall scripts.")
+                        .long("ignore-scripts")
+                        .takes_value(false),
+                )
         )
         .subcommand(SubCommand::with_name("clear-url-cache").about("Clears the cache of downloaded urls. Does not remove any installed binaries."))
         .subcommand(SubCommand::with_name("recreate-shims").about("Recreates all the shims."))
+        .subcommand(
+            SubCommand::with_name("hook")
+                .about("Outputs a script that applies the environment of the current directory's binaries when changing directories.")
+                .arg(
+                    Arg::with_name("shell")
+                        .help("The shell to output the script for.")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(&["bash", "zsh", "fish", "powershell"])
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("completions")
+                .about("Outputs a shell completions script.")
+                .arg(
+                    Arg::with_name("shell")
+                        .help("The shell to output the script for.")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(&["bash", "zsh", "fish", "powershell"])
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Runs a command of the configuration file's binaries or otherwise the global version.")
+                .setting(AppSettings::TrailingVarArg)
+                .arg(
+                    Arg::with_name("command")
+                        .help("The name of the command to run.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .help("The arguments to provide to the command.")
+                        .multiple(true)
+                        .allow_hyphen_values(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("env")
+                .about("Outputs the paths and environment variables of the binaries for use in CI systems and other shells.")
+                .arg(
+                    Arg::with_name("config")
+                        .help("Output the environment of the configuration file's binaries instead of the global versions.")
+                        .long("config")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("The format to output. Defaults to sh or powershell on Windows.")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["sh", "fish", "powershell", "github", "dotenv", "json"])
+                ),
+        )
         .subcommand(
             SubCommand::with_name("registry")
                 .about("Commands related to storing urls to binary version registries.")
@@ -480,6 +1045,169 @@ ARGS:
                     SubCommand::with_name("list")
                         .about("List all the urls to registries.")
                 )
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Creates an empty registry.json file in the specified directory.")
+                        .arg(
+                            Arg::with_name("dir")
+                                .help("The directory to create the registry file in.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("add-version")
+                        .about("Adds the version of a binary to a registry file based on its binary manifest file.")
+                        .arg(
+                            Arg::with_name("file")
+                                .help("The path to the registry file.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("url")
+                                .help("The url or file path of the binary manifest file.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("published-url")
+                                .help("The url the binary manifest file is published at. Required when providing a file path.")
+                                .long("published-url")
+                                .value_name("url")
+                                .takes_value(true)
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("plugin")
+                .about("Commands related to authoring binary manifest files.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("new")
+                        .about("Outputs a new binary manifest file based on the archive for each platform.")
+                        .arg(
+                            Arg::with_name("binary_name")
+                                .help("The binary name in the format <owner>/<name>.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("version")
+                                .help("The version of the binary.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("description")
+                                .help("The description of the binary.")
+                                .long("description")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("linux-x86_64")
+                                .help("The url or file path of the linux archive.")
+                                .long("linux-x86_64")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("darwin-x86_64")
+                                .help("The url or file path of the mac archive.")
+                                .long("darwin-x86_64")
+                                .takes_value(true)
+                        )
+                        .arg(
+                            Arg::with_name("windows-x86_64")
+                                .help("The url or file path of the windows archive.")
+                                .long("windows-x86_64")
+                                .takes_value(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("contents")
+                        .about("Lists the files in an archive to help choose the path of a command.")
+                        .arg(
+                            Arg::with_name("url")
+                                .help("The url or file path of the archive.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("checksum")
+                        .about("Downloads the archives in a binary manifest file and updates their checksums.")
+                        .arg(
+                            Arg::with_name("file")
+                                .help("The path to the binary manifest file.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("validate")
+                        .about("Downloads the archives in a binary manifest file and verifies the file matches their contents.")
+                        .arg(
+                            Arg::with_name("file")
+                                .help("The path to the binary manifest file.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Searches the names and descriptions of the binaries in the associated registries.")
+                .arg(
+                    Arg::with_name("term")
+                        .help("The text to search for.")
+                        .takes_value(true)
+                        .required(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("info")
+                .about("Outputs information about a binary and its versions.")
+                .arg(
+                    Arg::with_name("binary_name")
+                        .help("The binary name.")
+                        .takes_value(true)
+                        .required(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("Commands related to the user settings.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("get")
+                        .about("Outputs the value of a setting.")
+                        .arg(
+                            Arg::with_name("key")
+                                .help("The setting name.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("Sets the value of a setting.")
+                        .arg(
+                            Arg::with_name("key")
+                                .help("The setting name.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::with_name("value")
+                                .help("The value of the setting.")
+                                .takes_value(true)
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List all the settings and their values.")
+                )
         )
         .subcommand(
             SubCommand::with_name("hidden")
@@ -504,6 +1232,12 @@ ARGS:
                 .subcommand(
                     SubCommand::with_name("get-env-vars")
                 )
+                .subcommand(
+                    SubCommand::with_name("hook-env")
+                )
+                .subcommand(
+                    SubCommand::with_name("protocol-version")
+                )
                 .subcommand(
                     SubCommand::with_name("get-exec-env-changes")
                         .arg(
@@ -0,0 +1,15 @@
+
+_bvm_dynamic() {
+  local candidate
+  local candidates=()
+  while IFS= read -r candidate; do
+    candidates+=("$candidate")
+  done < <("__BVM_BIN__" hidden complete "$((COMP_CWORD - 1))" "${COMP_WORDS[@]:1}")
+  if [[ ${#candidates[@]} -gt 0 ]]; then
+    COMPREPLY=("${candidates[@]}")
+  else
+    _bvm "$@"
+  fi
+}
+
+complete -F _bvm_dynamic -o bashdefault -o default bvm
@@ -0,0 +1,7 @@
+
+function __bvm_complete_dynamic
+  set -l words (commandline -opc)[2..-1]
+  "__BVM_BIN__" hidden complete (count $words) $words (commandline -ct)
+end
+
+complete -c bvm -a "(__bvm_complete_dynamic)"
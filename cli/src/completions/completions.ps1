@@ -0,0 +1,15 @@
+
+Register-ArgumentCompleter -Native -CommandName 'bvm' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })
+    $index = if ($wordToComplete) { $words.Count - 1 } else { $words.Count }
+    $candidates = @(& "__BVM_BIN__" hidden complete $index @words)
+    if ($candidates.Count -gt 0) {
+        $candidates | ForEach-Object {
+            [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+        }
+    } else {
+        & $global:__bvm_static_completer $wordToComplete $commandAst $cursorPosition
+    }
+}
@@ -0,0 +1,147 @@
+use dprint_cli_core::types::ErrBox;
+
+use crate::arg_parser::{get_static_completions, parse_name_selector, Shell};
+use crate::environment::Environment;
+use crate::plugins::{BinaryManifestItem, PluginsManifest};
+use crate::registry::Registry;
+use crate::types::BinaryName;
+
+const BVM_BIN_PLACEHOLDER: &str = "__BVM_BIN__";
+const POWERSHELL_STATIC_COMPLETER_REGISTRATION: &str =
+  "Register-ArgumentCompleter -Native -CommandName 'bvm' -ScriptBlock {";
+
+/// Gets the completions script for the provided shell.
+///
+/// The static completions for the sub commands and flags are generated from
+/// the argument parser then wrapped so that binary names, versions, and
+/// command names are completed by calling back into `bvm hidden complete`.
+pub fn get_completions_script(environment: &impl Environment, shell: &Shell) -> Result<String, ErrBox> {
+  let static_completions = get_static_completions(shell);
+  let static_completions = static_completions.trim_end();
+  let script = match shell {
+    Shell::Bash => format!("{}\n{}", static_completions, include_str!("completions.bash")),
+    Shell::Zsh => format!(
+      "{}\n{}",
+      static_completions
+        .strip_suffix("_bvm \"$@\"")
+        .unwrap_or(static_completions),
+      include_str!("completions.zsh")
+    ),
+    Shell::Fish => format!("{}\n{}", static_completions, include_str!("completions.fish")),
+    Shell::Powershell => format!(
+      "{}\n{}",
+      static_completions.replace(
+        POWERSHELL_STATIC_COMPLETER_REGISTRATION,
+        "$global:__bvm_static_completer = {"
+      ),
+      include_str!("completions.ps1")
+    ),
+  };
+  let bvm_bin = environment.current_exe()?;
+  Ok(
+    script
+      .trim_end()
+      .replace(BVM_BIN_PLACEHOLDER, &bvm_bin.to_string_lossy()),
+  )
+}
+
+/// Gets the dynamic completion candidates for the word at `index` in the
+/// words following `bvm`.
+pub fn get_completion_candidates(environment: &impl Environment, index: usize, words: &[String]) -> Vec<String> {
+  let current = words.get(index).map(String::as_str).unwrap_or("");
+  let previous = words
+    .iter()
+    .take(index)
+    .filter(|word| !word.starts_with('-'))
+    .map(String::as_str)
+    .collect::<Vec<_>>();
+
+  let mut candidates = match previous.as_slice() {
+    ["use" | "exec" | "uninstall" | "reinstall" | "unlink"] => get_installed_binary_names(environment),
+    ["use" | "exec", name] => {
+      let mut versions = get_installed_versions(environment, name);
+      versions.push("path".to_string());
+      versions
+    }
+    ["uninstall" | "reinstall" | "unlink", name] => get_installed_versions(environment, name),
+    ["exec", name, version] => get_command_names(environment, name, version),
+    ["run"] => get_all_command_names(environment),
+    ["install" | "add"] => get_registry_binary_names(environment),
+    ["info"] => {
+      let mut names = get_registry_binary_names(environment);
+      names.extend(get_installed_binary_names(environment));
+      names
+    }
+    _ => Vec::new(),
+  };
+
+  candidates.retain(|candidate| candidate.starts_with(current));
+  candidates.sort();
+  candidates.dedup();
+  candidates
+}
+
+fn get_installed_binary_names(environment: &impl Environment) -> Vec<String> {
+  let plugin_manifest = PluginsManifest::load(environment);
+  get_display_names(plugin_manifest.binaries().map(|item| &item.name).collect())
+}
+
+/// Gets the names without the owner unless another owner has a binary with the same name.
+fn get_display_names(names: Vec<&BinaryName>) -> Vec<String> {
+  names
+    .iter()
+    .map(|name| {
+      let is_ambiguous = names
+        .iter()
+        .any(|other| other.name == name.name && other.owner != name.owner);
+      if is_ambiguous {
+        name.to_string()
+      } else {
+        name.name.clone()
+      }
+    })
+    .collect()
+}
+
+fn get_installed_versions(environment: &impl Environment, name: &str) -> Vec<String> {
+  get_matching_binaries(environment, name)
+    .into_iter()
+    .map(|item| item.version.to_string())
+    .collect()
+}
+
+fn get_command_names(environment: &impl Environment, name: &str, version: &str) -> Vec<String> {
+  get_matching_binaries(environment, name)
+    .into_iter()
+    .filter(|item| version == "path" || item.version.to_string() == version)
+    .flat_map(|item| {
+      item
+        .commands
+        .into_iter()
+        .map(|command| command.name.as_str().to_string())
+    })
+    .collect()
+}
+
+fn get_all_command_names(environment: &impl Environment) -> Vec<String> {
+  let plugin_manifest = PluginsManifest::load(environment);
+  plugin_manifest
+    .binaries()
+    .flat_map(|item| item.commands.iter().map(|command| command.name.as_str().to_string()))
+    .collect()
+}
+
+fn get_matching_binaries(environment: &impl Environment, name: &str) -> Vec<BinaryManifestItem> {
+  let name_selector = parse_name_selector(name.to_string());
+  let plugin_manifest = PluginsManifest::load(environment);
+  plugin_manifest
+    .binaries()
+    .filter(|item| name_selector.is_match(&item.name))
+    .cloned()
+    .collect()
+}
+
+fn get_registry_binary_names(environment: &impl Environment) -> Vec<String> {
+  let items = Registry::load(environment).items();
+  get_display_names(items.iter().map(|item| item.name()).collect())
+}
@@ -0,0 +1,166 @@
+use dprint_cli_core::types::ErrBox;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::arg_parser::EnvFormat;
+use crate::environment::{Environment, SYS_PATH_DELIMITER};
+use crate::utils::{quote_fish, quote_powershell, quote_sh};
+
+/// The paths and environment variables of a set of binaries.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct BinariesEnv {
+  /// Paths to prepend to the PATH in order of precedence.
+  pub paths: Vec<String>,
+  pub variables: BTreeMap<String, String>,
+}
+
+/// Outputs the environment in the provided format.
+///
+/// The GitHub Actions format appends to the files in the `GITHUB_PATH` and
+/// `GITHUB_ENV` environment variables instead of logging.
+pub fn output_env(environment: &impl Environment, format: &EnvFormat, env: &BinariesEnv) -> Result<(), ErrBox> {
+  let text = match format {
+    EnvFormat::Sh => get_sh_text(env),
+    EnvFormat::Fish => get_fish_text(env),
+    EnvFormat::Powershell => get_powershell_text(env),
+    EnvFormat::Dotenv => get_dotenv_text(environment, env),
+    EnvFormat::Json => serde_json::to_string_pretty(env)?,
+    EnvFormat::Github => {
+      // each line is prepended to the path, so add them in reverse
+      let paths = env
+        .paths
+        .iter()
+        .rev()
+        .map(|path| format!("{}\n", path))
+        .collect::<String>();
+      append_github_file(environment, "GITHUB_PATH", &paths)?;
+      let variables = env
+        .variables
+        .iter()
+        .map(|(key, value)| get_github_env_line(key, value))
+        .collect::<String>();
+      append_github_file(environment, "GITHUB_ENV", &variables)?;
+      return Ok(());
+    }
+  };
+
+  if !text.is_empty() {
+    environment.log(&text);
+  }
+  Ok(())
+}
+
+fn get_sh_text(env: &BinariesEnv) -> String {
+  let mut lines = Vec::new();
+  if !env.paths.is_empty() {
+    lines.push(format!(
+      "export PATH={}\"{}$PATH\"",
+      quote_sh(&env.paths.join(SYS_PATH_DELIMITER)),
+      SYS_PATH_DELIMITER
+    ));
+  }
+  for (key, value) in env.variables.iter() {
+    lines.push(format!("export {}={}", key, quote_sh(value)));
+  }
+  lines.join("\n")
+}
+
+fn get_fish_text(env: &BinariesEnv) -> String {
+  let mut lines = Vec::new();
+  if !env.paths.is_empty() {
+    let paths = env.paths.iter().map(|path| quote_fish(path)).collect::<Vec<_>>();
+    lines.push(format!("set -gx PATH {} $PATH", paths.join(" ")));
+  }
+  for (key, value) in env.variables.iter() {
+    lines.push(format!("set -gx {} {}", key, quote_fish(value)));
+  }
+  lines.join("\n")
+}
+
+fn get_powershell_text(env: &BinariesEnv) -> String {
+  let mut lines = Vec::new();
+  if !env.paths.is_empty() {
+    lines.push(format!(
+      "$env:PATH = {} + $env:PATH",
+      quote_powershell(&format!("{}{}", env.paths.join(SYS_PATH_DELIMITER), SYS_PATH_DELIMITER))
+    ));
+  }
+  for (key, value) in env.variables.iter() {
+    lines.push(format!("${{env:{}}} = {}", key, quote_powershell(value)));
+  }
+  lines.join("\n")
+}
+
+fn get_dotenv_text(environment: &impl Environment, env: &BinariesEnv) -> String {
+  let mut lines = Vec::new();
+  if !env.paths.is_empty() {
+    // dotenv files don't support expansion, so include the current path
+    let mut paths = env.paths.clone();
+    let current_path = environment.get_env_path();
+    if !current_path.is_empty() {
+      paths.push(current_path);
+    }
+    lines.push(format!("PATH={}", quote_dotenv(&paths.join(SYS_PATH_DELIMITER))));
+  }
+  for (key, value) in env.variables.iter() {
+    lines.push(format!("{}={}", key, quote_dotenv(value)));
+  }
+  lines.join("\n")
+}
+
+fn quote_dotenv(text: &str) -> String {
+  let needs_quotes = text.is_empty()
+    || text
+      .chars()
+      .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '#' | '$' | '`'));
+  if needs_quotes {
+    format!(
+      "\"{}\"",
+      text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+        .replace('`', "\\`")
+        .replace('\n', "\\n")
+    )
+  } else {
+    text.to_string()
+  }
+}
+
+fn get_github_env_line(key: &str, value: &str) -> String {
+  if value.contains('\n') {
+    let mut delimiter = String::from("BVM_EOF");
+    while value.lines().any(|line| line == delimiter) {
+      delimiter.push('_');
+    }
+    format!("{}<<{}\n{}\n{}\n", key, delimiter, value, delimiter)
+  } else {
+    format!("{}={}\n", key, value)
+  }
+}
+
+fn append_github_file(environment: &impl Environment, env_var_name: &str, text: &str) -> Result<(), ErrBox> {
+  let file_path = match environment.get_env_var(env_var_name) {
+    Some(file_path) if !file_path.is_empty() => file_path,
+    _ => {
+      return err!(
+        "The {} environment variable was not set. The github format is for use in GitHub Actions.",
+        env_var_name
+      )
+    }
+  };
+  if text.is_empty() {
+    return Ok(());
+  }
+  let mut file_text = if environment.path_exists(&file_path) {
+    environment.read_file_text(&file_path)?
+  } else {
+    String::new()
+  };
+  if !file_text.is_empty() && !file_text.ends_with('\n') {
+    file_text.push('\n');
+  }
+  file_text.push_str(text);
+  environment.write_file_text(&file_path, &file_text)
+}
@@ -1,2 +1,42 @@
 /// The separator used for the system path
 pub const SYS_PATH_DELIMITER: &'static str = if cfg!(target_os = "windows") { ";" } else { ":" };
+
+/// Environment variable changes to apply when running a command.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EnvChanges {
+  pub set: Vec<(String, String)>,
+  pub removed: Vec<String>,
+}
+
+/// An executable for a shim to run in place of itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecCommand {
+  pub executable_path: std::path::PathBuf,
+  /// The name the shim was invoked with, which is passed on as the executable's
+  /// first argument on platforms that support it.
+  #[cfg_attr(target_os = "windows", allow(dead_code))]
+  pub arg0: std::ffi::OsString,
+  pub args: Vec<std::ffi::OsString>,
+  pub env_changes: EnvChanges,
+}
+
+/// A shell command such as an install script to run to completion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShellCommand {
+  pub cwd: std::path::PathBuf,
+  pub command: String,
+  /// Environment variables to set in addition to the current ones.
+  pub env_vars: Vec<(String, String)>,
+  /// File that stdout and stderr are written to.
+  pub log_file_path: std::path::PathBuf,
+  /// How long to wait before killing the command or None to wait forever.
+  pub timeout: Option<std::time::Duration>,
+}
+
+/// Information about a file on the file system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FileMetadata {
+  pub len: u64,
+  /// Seconds since the unix epoch that the file was last modified.
+  pub modified_time_secs: u64,
+}
@@ -0,0 +1,709 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_cli_core::logging::{ProgressBar, ProgressBarStyle, ProgressBars};
+use dprint_cli_core::types::ErrBox;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use url::Url;
+
+use super::{Environment, FileStream};
+use crate::settings::Settings;
+
+const MAX_REDIRECTS: usize = 10;
+const MAX_RETRY_DELAY_SECS: u64 = 30;
+const ABANDONED_DOWNLOAD_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+enum DownloadError {
+  /// A connection error or server error that may succeed when tried again.
+  Retryable(ErrBox),
+  Fatal(ErrBox),
+}
+
+/// Downloads the url following redirects manually so that the headers are
+/// only sent to the origin of the provided url.
+///
+/// Connection and server errors are retried with an exponential backoff. The
+/// response is written to a file in the local user data directory that's unique
+/// to this download so that concurrent downloads of the same url don't write to
+/// the same file. Retries resume from where the previous attempt stopped and a
+/// download that fails is kept so that a later run can resume it. The completed
+/// file is streamed from disk and deleted once the stream is dropped.
+pub fn download_url(
+  environment: &impl Environment,
+  url: &str,
+  headers: &[(String, String)],
+  progress_bars: &Option<ProgressBars>,
+) -> Result<FileStream, ErrBox> {
+  let settings = environment.get_settings();
+  let files = DownloadFiles::new(environment, url);
+  remove_old_partial_files(environment, &files, settings.cache_size());
+  files.claim_resumable(environment);
+  let max_attempts = settings.download_retries() + 1;
+  let mut attempt = 1;
+
+  loop {
+    log_verbose!(
+      environment,
+      "Downloading {} (attempt {} of {})",
+      url,
+      attempt,
+      max_attempts
+    );
+    match try_download(environment, &settings, url, headers, progress_bars, &files.file_path) {
+      Ok(()) => {
+        let _ = environment.remove_file(get_validator_path(&files.file_path));
+        let file_path = files.file_path.with_extension("download");
+        environment.rename_file(&files.file_path, &file_path)?;
+        return environment.open_temp_file(file_path);
+      }
+      Err(DownloadError::Retryable(err)) if attempt < max_attempts => {
+        let delay = get_retry_delay(attempt);
+        log_verbose!(
+          environment,
+          "Attempt {} of {} failed. {} Retrying in {}s...",
+          attempt,
+          max_attempts,
+          err.to_string(),
+          delay.as_secs()
+        );
+        environment.sleep(delay);
+        attempt += 1;
+      }
+      Err(DownloadError::Retryable(err)) => {
+        files.keep_resumable(environment);
+        return Err(err);
+      }
+      Err(DownloadError::Fatal(err)) => {
+        files.remove(environment);
+        return Err(err);
+      }
+    }
+  }
+}
+
+fn try_download(
+  environment: &impl Environment,
+  settings: &Settings,
+  url: &str,
+  headers: &[(String, String)],
+  progress_bars: &Option<ProgressBars>,
+  partial_file_path: &Path,
+) -> Result<(), DownloadError> {
+  let original_url = Url::parse(url).map_err(|err| DownloadError::Fatal(err.into()))?;
+  let mut current_url = original_url.clone();
+  let validator_path = get_validator_path(partial_file_path);
+  // only resume when it can be confirmed the remote file hasn't changed since
+  let validator = environment.read_file_text(&validator_path).ok();
+  let resume_position = match &validator {
+    Some(_) => environment
+      .get_file_metadata(partial_file_path)
+      .map(|metadata| metadata.len)
+      .unwrap_or(0),
+    None => 0,
+  };
+
+  for _ in 0..=MAX_REDIRECTS {
+    let agent = build_agent(environment, settings, &current_url).map_err(DownloadError::Fatal)?;
+    let mut request = agent.get(current_url.as_str());
+    if current_url.origin() == original_url.origin() {
+      for (name, value) in headers {
+        request = request.set(name, value);
+      }
+    }
+    if let (true, Some(validator)) = (resume_position > 0, &validator) {
+      log_verbose!(environment, "Resuming download of {} at byte {}.", url, resume_position);
+      // the server sends the whole file when it no longer matches the validator
+      request = request
+        .set("Range", &format!("bytes={}-", resume_position))
+        .set("If-Range", validator);
+    }
+    let resp = match request.call() {
+      Ok(resp) => resp,
+      Err(ureq::Error::Status(416, _)) if resume_position > 0 => {
+        // the partial file is no longer valid for the remote file, so start over
+        let _ = environment.remove_file(partial_file_path);
+        let _ = environment.remove_file(&validator_path);
+        return Err(DownloadError::Retryable(err_obj!(
+          "Error downloading {}. The partial download could not be resumed.",
+          url
+        )));
+      }
+      Err(ureq::Error::Status(status, _)) => {
+        let err = err_obj!("Error downloading {}. Status: {}", url, status);
+        return Err(if status >= 500 || status == 429 {
+          DownloadError::Retryable(err)
+        } else {
+          DownloadError::Fatal(err)
+        });
+      }
+      Err(err) => {
+        return Err(DownloadError::Retryable(err_obj!(
+          "Error downloading {}. Error: {}",
+          url,
+          err
+        )))
+      }
+    };
+
+    if (300..400).contains(&resp.status()) {
+      let location = match resp.header("location") {
+        Some(location) => location,
+        None => {
+          return Err(DownloadError::Fatal(err_obj!(
+            "Error downloading {}. The redirect did not have a location.",
+            url
+          )))
+        }
+      };
+      current_url = current_url
+        .join(location)
+        .map_err(|err| DownloadError::Fatal(err.into()))?;
+      continue;
+    }
+
+    let start_position = match resp.status() {
+      206 => {
+        if get_content_range_start(&resp) != Some(resume_position) {
+          let _ = environment.remove_file(partial_file_path);
+          let _ = environment.remove_file(&validator_path);
+          return Err(DownloadError::Retryable(err_obj!(
+            "Error downloading {}. The server did not resume the download at byte {}.",
+            url,
+            resume_position
+          )));
+        }
+        resume_position
+      }
+      // the server may ignore the range header and send the whole file
+      200 => {
+        match get_validator(&resp) {
+          Some(validator) => environment
+            .write_file_text(&validator_path, &validator)
+            .map_err(DownloadError::Fatal)?,
+          None => {
+            let _ = environment.remove_file(&validator_path);
+          }
+        }
+        0
+      }
+      status => {
+        return Err(DownloadError::Fatal(err_obj!(
+          "Error downloading {}. Status: {}",
+          url,
+          status
+        )))
+      }
+    };
+    let total_size = resp
+      .header("Content-Length")
+      .and_then(|s| s.parse::<u64>().ok())
+      .map(|size| size + start_position)
+      .unwrap_or(0);
+    let progress_bar = progress_bars.as_ref().map(|progress_bars| {
+      let message = format!("Downloading {}", url);
+      progress_bars.add_progress(message, ProgressBarStyle::Download, total_size as usize)
+    });
+    let mut reader = DownloadReader {
+      inner: resp.into_reader(),
+      position: start_position as usize,
+      progress_bar,
+      read_error: None,
+    };
+    return write_download(environment, url, &mut reader, partial_file_path, total_size);
+  }
+
+  Err(DownloadError::Fatal(err_obj!(
+    "Error downloading {}. Too many redirects.",
+    url
+  )))
+}
+
+fn write_download(
+  environment: &impl Environment,
+  url: &str,
+  reader: &mut DownloadReader<impl Read>,
+  partial_file_path: &Path,
+  total_size: u64,
+) -> Result<(), DownloadError> {
+  let result = if reader.position > 0 {
+    environment.append_file_from_reader(partial_file_path, reader)
+  } else {
+    environment.write_file_from_reader(partial_file_path, reader)
+  };
+  if let Some(pb) = reader.progress_bar.take() {
+    pb.finish();
+  }
+  // errors reading the response may succeed when tried again unlike errors writing the file
+  if let Some(err) = reader.read_error.take() {
+    return Err(DownloadError::Retryable(err_obj!(
+      "Error downloading {}. {}",
+      url,
+      err.to_string()
+    )));
+  }
+  if let Err(err) = result {
+    return Err(DownloadError::Fatal(err_obj!(
+      "Error writing the download of {}: {}",
+      url,
+      err.to_string()
+    )));
+  }
+  // the http client doesn't check this when the server closes the connection
+  if total_size > 0 && reader.position as u64 != total_size {
+    return Err(DownloadError::Retryable(err_obj!(
+      "Error downloading {}. The connection closed after {} of {} bytes.",
+      url,
+      reader.position,
+      total_size
+    )));
+  }
+  Ok(())
+}
+
+/// Reads the response while updating the progress bar and keeping any error
+/// so that it can be told apart from an error writing the file.
+struct DownloadReader<TRead: Read> {
+  inner: TRead,
+  position: usize,
+  progress_bar: Option<ProgressBar>,
+  read_error: Option<std::io::Error>,
+}
+
+impl<TRead: Read> Read for DownloadReader<TRead> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    match self.inner.read(buf) {
+      Ok(bytes_read) => {
+        self.position += bytes_read;
+        if let Some(pb) = &self.progress_bar {
+          pb.set_position(self.position);
+        }
+        Ok(bytes_read)
+      }
+      Err(err) => {
+        let result = Err(std::io::Error::new(err.kind(), err.to_string()));
+        self.read_error = Some(err);
+        result
+      }
+    }
+  }
+}
+
+/// Gets the delay before the next attempt, doubling after each failure.
+fn get_retry_delay(attempt: u32) -> Duration {
+  let secs = 2u64.saturating_pow(attempt - 1).min(MAX_RETRY_DELAY_SECS);
+  Duration::from_secs(secs)
+}
+
+/// Gets the value to send in the `If-Range` header when resuming the download.
+/// Weak entity tags can't be used for range requests.
+fn get_validator(resp: &ureq::Response) -> Option<String> {
+  match resp.header("ETag") {
+    Some(etag) if !etag.starts_with("W/") => Some(etag.to_string()),
+    _ => resp.header("Last-Modified").map(String::from),
+  }
+}
+
+/// Gets the first byte position of a `Content-Range: bytes <start>-<end>/<size>` header.
+fn get_content_range_start(resp: &ureq::Response) -> Option<u64> {
+  let value = resp.header("Content-Range")?.trim().strip_prefix("bytes ")?;
+  value[..value.find('-')?].trim().parse::<u64>().ok()
+}
+
+fn get_validator_path(file_path: &Path) -> PathBuf {
+  file_path.with_extension("validator")
+}
+
+static DOWNLOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The files of a download in the downloads directory. A file and its
+/// validator (the `ETag` or `Last-Modified` header of the response) are
+/// only ever renamed as a whole, which is atomic.
+struct DownloadFiles {
+  dir_path: PathBuf,
+  /// The partial file of a previous download of the url that can be resumed.
+  resumable_path: PathBuf,
+  /// The file this download is written to.
+  file_path: PathBuf,
+}
+
+impl DownloadFiles {
+  fn new(environment: &impl Environment, url: &str) -> DownloadFiles {
+    let dir_path = environment.get_local_user_data_dir().join("downloads");
+    let _ = environment.create_dir_all(&dir_path);
+    let name = get_sha256_checksum(url.as_bytes());
+    let id = DOWNLOAD_COUNT.fetch_add(1, Ordering::SeqCst);
+    DownloadFiles {
+      resumable_path: dir_path.join(format!("{}.partial", name)),
+      file_path: dir_path.join(format!("{}.{}-{}.downloading", name, process::id(), id)),
+      dir_path,
+    }
+  }
+
+  /// Takes the partial file of a previous download so that this download
+  /// resumes it. Only one concurrent download can take it.
+  fn claim_resumable(&self, environment: &impl Environment) {
+    if environment.rename_file(&self.resumable_path, &self.file_path).is_ok()
+      && environment
+        .rename_file(
+          get_validator_path(&self.resumable_path),
+          get_validator_path(&self.file_path),
+        )
+        .is_err()
+    {
+      let _ = environment.remove_file(&self.file_path);
+    }
+  }
+
+  /// Keeps the file so that a later download of the url can resume it.
+  fn keep_resumable(&self, environment: &impl Environment) {
+    if environment
+      .rename_file(
+        get_validator_path(&self.file_path),
+        get_validator_path(&self.resumable_path),
+      )
+      .is_ok()
+    {
+      let _ = environment.rename_file(&self.file_path, &self.resumable_path);
+    } else {
+      self.remove(environment);
+    }
+  }
+
+  fn remove(&self, environment: &impl Environment) {
+    let _ = environment.remove_file(&self.file_path);
+    let _ = environment.remove_file(get_validator_path(&self.file_path));
+  }
+}
+
+/// Removes the least recently modified partial files other than the one for
+/// the url until the partial files use at most the maximum number of bytes.
+/// Files of downloads that haven't been written to in a day are also removed
+/// since they were left behind by a process that exited before finishing.
+fn remove_old_partial_files(environment: &impl Environment, files: &DownloadFiles, max_bytes: u64) {
+  let paths = match environment.read_dir(&files.dir_path) {
+    Ok(paths) => paths,
+    Err(_) => return,
+  };
+  let mut partial_files = Vec::new();
+  for path in paths {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    let metadata = match environment.get_file_metadata(&path) {
+      Some(metadata) => metadata,
+      None => continue,
+    };
+    if extension == "downloading" {
+      let age = environment.get_time_secs().saturating_sub(metadata.modified_time_secs);
+      if age > ABANDONED_DOWNLOAD_AGE.as_secs() {
+        let _ = environment.remove_file(&path);
+        let _ = environment.remove_file(get_validator_path(&path));
+      }
+    } else if extension == "partial" && path != files.resumable_path {
+      partial_files.push((metadata.modified_time_secs, metadata.len, path));
+    }
+  }
+  let mut total_bytes = partial_files.iter().map(|(_, len, _)| len).sum::<u64>();
+  partial_files.sort();
+  for (_, len, path) in partial_files {
+    if total_bytes <= max_bytes {
+      break;
+    }
+    if environment.remove_file(&path).is_ok() {
+      let _ = environment.remove_file(get_validator_path(&path));
+      total_bytes -= len;
+    }
+  }
+}
+
+fn build_agent(environment: &impl Environment, settings: &Settings, url: &Url) -> Result<ureq::Agent, ErrBox> {
+  let mut agent = ureq::AgentBuilder::new()
+    .redirects(0)
+    .timeout_connect(settings.connect_timeout())
+    .timeout_read(settings.read_timeout());
+  if let Some(proxy_url) = get_proxy_url(environment, url) {
+    agent = agent.proxy(ureq::Proxy::new(proxy_url)?);
+  }
+  Ok(agent.build())
+}
+
+fn get_proxy_url(environment: &impl Environment, url: &Url) -> Option<String> {
+  let env_var_name = match url.scheme() {
+    "https" => "HTTPS_PROXY",
+    "http" => "HTTP_PROXY",
+    _ => return None,
+  };
+  environment
+    .get_env_var(env_var_name)
+    .or_else(|| environment.get_env_var(&env_var_name.to_lowercase()))
+}
+
+#[cfg(test)]
+mod test {
+  use super::super::test_server::*;
+  use super::super::TestEnvironment;
+  use super::*;
+
+  fn authorization() -> Vec<(String, String)> {
+    vec![("Authorization".to_string(), "Bearer secret".to_string())]
+  }
+
+  #[test]
+  fn headers_not_sent_to_other_origins_on_redirect() {
+    let environment = TestEnvironment::new();
+    let other_server = TestServer::start(|_| TestResponse::new(200, "other"));
+    let other_url = other_server.url("/file");
+    let server = TestServer::start(move |request| match request.path.as_str() {
+      "/same-origin" => TestResponse::new(302, "").header("Location", "/file"),
+      "/other-origin" => TestResponse::new(302, "").header("Location", &other_url),
+      _ => TestResponse::new(200, "same"),
+    });
+
+    let stream = download_url(&environment, &server.url("/same-origin"), &authorization(), &None).unwrap();
+    assert_eq!(stream.into_bytes().unwrap(), b"same");
+    let requests = server.take_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].path, "/file");
+    assert_eq!(requests[1].header("Authorization"), Some("Bearer secret"));
+
+    let stream = download_url(&environment, &server.url("/other-origin"), &authorization(), &None).unwrap();
+    assert_eq!(stream.into_bytes().unwrap(), b"other");
+    let requests = server.take_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].header("Authorization"), Some("Bearer secret"));
+    let other_requests = other_server.take_requests();
+    assert_eq!(other_requests.len(), 1);
+    assert_eq!(other_requests[0].header("Authorization"), None);
+  }
+
+  fn download(environment: &TestEnvironment, server: &TestServer) -> Result<Vec<u8>, ErrBox> {
+    download_url(environment, &server.url("/file"), &[], &None)?.into_bytes()
+  }
+
+  fn get_downloads_dir_entries(environment: &TestEnvironment) -> Vec<String> {
+    let mut names = environment
+      .read_dir("/local-data/downloads")
+      .unwrap()
+      .into_iter()
+      .map(|path| path.extension().unwrap().to_string_lossy().to_string())
+      .collect::<Vec<_>>();
+    names.sort();
+    names
+  }
+
+  /// Sends the first four bytes of the file and then closes the connection.
+  fn truncated_response() -> TestResponse {
+    let mut response = TestResponse::new(200, "0123456789").header("ETag", "\"v1\"");
+    response.truncate_at = Some(4);
+    response
+  }
+
+  #[test]
+  fn retry_delay_doubles_up_to_max() {
+    assert_eq!(get_retry_delay(1), Duration::from_secs(1));
+    assert_eq!(get_retry_delay(2), Duration::from_secs(2));
+    assert_eq!(get_retry_delay(3), Duration::from_secs(4));
+    assert_eq!(get_retry_delay(6), Duration::from_secs(30));
+    assert_eq!(get_retry_delay(100), Duration::from_secs(30));
+  }
+
+  #[test]
+  fn retries_server_errors() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "2");
+    let count = AtomicUsize::new(0);
+    let server = TestServer::start(move |request| match request.path.as_str() {
+      "/missing" => TestResponse::new(404, ""),
+      _ if count.fetch_add(1, Ordering::SeqCst) == 0 => TestResponse::new(503, ""),
+      _ => TestResponse::new(200, "text"),
+    });
+
+    assert_eq!(download(&environment, &server).unwrap(), b"text");
+    assert_eq!(server.take_requests().len(), 2);
+    assert_eq!(environment.take_sleeps(), vec![Duration::from_secs(1)]);
+
+    // client errors are not retried
+    let err = download_url(&environment, &server.url("/missing"), &[], &None)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!("Error downloading {}. Status: 404", server.url("/missing"))
+    );
+    assert_eq!(server.take_requests().len(), 1);
+    assert_eq!(environment.take_sleeps(), Vec::<Duration>::new());
+  }
+
+  #[test]
+  fn backs_off_between_retries() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "3");
+    let server = TestServer::start(|_| TestResponse::new(503, ""));
+
+    let err = download(&environment, &server).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!("Error downloading {}. Status: 503", server.url("/file"))
+    );
+    assert_eq!(server.take_requests().len(), 4);
+    assert_eq!(
+      environment.take_sleeps(),
+      vec![Duration::from_secs(1), Duration::from_secs(2), Duration::from_secs(4)]
+    );
+  }
+
+  #[test]
+  fn resumes_failed_download() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "0");
+    let server = TestServer::start(|request| match (request.header("Range"), request.header("If-Range")) {
+      (Some("bytes=4-"), Some("\"v1\"")) => TestResponse::new(206, "456789").header("Content-Range", "bytes 4-9/10"),
+      _ => truncated_response(),
+    });
+
+    assert!(download(&environment, &server).is_err());
+    assert_eq!(get_downloads_dir_entries(&environment), vec!["partial", "validator"]);
+    assert_eq!(download(&environment, &server).unwrap(), b"0123456789");
+    let requests = server.take_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("Range"), None);
+    assert_eq!(requests[1].header("Range"), Some("bytes=4-"));
+    assert_eq!(get_downloads_dir_entries(&environment), Vec::<String>::new());
+  }
+
+  #[test]
+  fn restarts_download_when_remote_file_changed() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "0");
+    let server = TestServer::start(|request| match request.header("If-Range") {
+      // the server sends the whole file because the validator doesn't match
+      Some(_) => TestResponse::new(200, "abcdefghij").header("ETag", "\"v2\""),
+      None => truncated_response(),
+    });
+
+    assert!(download(&environment, &server).is_err());
+    assert_eq!(download(&environment, &server).unwrap(), b"abcdefghij");
+    assert_eq!(server.take_requests()[1].header("If-Range"), Some("\"v1\""));
+  }
+
+  #[test]
+  fn restarts_download_resumed_at_wrong_position() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "0");
+    let count = AtomicUsize::new(0);
+    let server = TestServer::start(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+      0 => truncated_response(),
+      1 => TestResponse::new(206, "0123456789").header("Content-Range", "bytes 0-9/10"),
+      _ => TestResponse::new(200, "0123456789"),
+    });
+
+    assert!(download(&environment, &server).is_err());
+    let err = download(&environment, &server).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "Error downloading {}. The server did not resume the download at byte 4.",
+        server.url("/file")
+      )
+    );
+    assert_eq!(get_downloads_dir_entries(&environment), Vec::<String>::new());
+    assert_eq!(download(&environment, &server).unwrap(), b"0123456789");
+    assert_eq!(server.take_requests()[2].header("Range"), None);
+  }
+
+  #[test]
+  fn does_not_resume_without_validator() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "0");
+    let count = AtomicUsize::new(0);
+    let server = TestServer::start(move |_| {
+      let mut response = TestResponse::new(200, "0123456789");
+      if count.fetch_add(1, Ordering::SeqCst) == 0 {
+        response.truncate_at = Some(4);
+      }
+      response
+    });
+
+    assert!(download(&environment, &server).is_err());
+    assert_eq!(download(&environment, &server).unwrap(), b"0123456789");
+    assert_eq!(server.take_requests()[1].header("Range"), None);
+  }
+
+  #[test]
+  fn read_timeout() {
+    let environment = TestEnvironment::new();
+    environment.set_env_var("BVM_DOWNLOAD_RETRIES", "0");
+    environment.set_env_var("BVM_READ_TIMEOUT", "1");
+    let server = TestServer::start(|_| {
+      let mut response = TestResponse::new(200, "text");
+      response.delay = Some(Duration::from_secs(3));
+      response
+    });
+
+    let err = download(&environment, &server).err().unwrap();
+    assert!(err
+      .to_string()
+      .starts_with(&format!("Error downloading {}.", server.url("/file"))));
+    assert_eq!(server.take_requests().len(), 1);
+  }
+
+  #[test]
+  fn concurrent_downloads_use_different_files() {
+    let environment = TestEnvironment::new();
+    let files = DownloadFiles::new(&environment, "https://example.com/file");
+    let other_files = DownloadFiles::new(&environment, "https://example.com/file");
+    assert_eq!(files.resumable_path, other_files.resumable_path);
+    assert_ne!(files.file_path, other_files.file_path);
+
+    // only one of them can resume a previous download
+    environment.write_file_text(&files.resumable_path, "data").unwrap();
+    environment
+      .write_file_text(get_validator_path(&files.resumable_path), "\"v1\"")
+      .unwrap();
+    files.claim_resumable(&environment);
+    other_files.claim_resumable(&environment);
+    assert_eq!(environment.read_file_text(&files.file_path).unwrap(), "data");
+    assert!(!environment.path_exists(&other_files.file_path));
+  }
+
+  #[test]
+  fn removes_abandoned_downloads_and_old_partial_files() {
+    let environment = TestEnvironment::new();
+    let files = DownloadFiles::new(&environment, "https://example.com/file");
+    let dir_path = PathBuf::from("/local-data/downloads");
+    let now = environment.get_time_secs();
+    let write_file = |name: &str, len: usize, modified_time_secs: u64| {
+      let file_path = dir_path.join(name);
+      environment.write_file(&file_path, &vec![0; len]).unwrap();
+      environment.set_file_modified_time(&file_path, modified_time_secs);
+    };
+    write_file(
+      "abandoned.1-0.downloading",
+      1,
+      now - ABANDONED_DOWNLOAD_AGE.as_secs() - 1,
+    );
+    write_file("active.1-1.downloading", 1, now - 60);
+    write_file("oldest.partial", 4, now - 30);
+    write_file("older.partial", 4, now - 20);
+    write_file("newest.partial", 4, now - 10);
+    // the partial file of the url is kept regardless of its age
+    environment.write_file(&files.resumable_path, &[0; 4]).unwrap();
+    environment.set_file_modified_time(&files.resumable_path, 0);
+
+    remove_old_partial_files(&environment, &files, 5);
+    let mut file_names = environment
+      .read_dir(&dir_path)
+      .unwrap()
+      .into_iter()
+      .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+      .collect::<Vec<_>>();
+    file_names.sort();
+    let mut expected_file_names = vec![
+      "active.1-1.downloading".to_string(),
+      "newest.partial".to_string(),
+      files.resumable_path.file_name().unwrap().to_string_lossy().to_string(),
+    ];
+    expected_file_names.sort();
+    assert_eq!(file_names, expected_file_names);
+  }
+}
@@ -1,33 +1,68 @@
 use dprint_cli_core::types::ErrBox;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use url::Url;
 
+use super::{ExecCommand, FileMetadata, FileStream, ShellCommand};
+use crate::settings::{get_credential_for_url, Settings};
+
 pub trait Environment: Clone + std::marker::Send + std::marker::Sync + 'static {
   fn is_real(&self) -> bool;
   fn read_file(&self, file_path: impl AsRef<Path>) -> Result<Vec<u8>, ErrBox>;
   fn read_file_text(&self, file_path: impl AsRef<Path>) -> Result<String, ErrBox>;
+  fn open_file(&self, file_path: impl AsRef<Path>) -> Result<FileStream, ErrBox>;
+  /// Opens a stream of the file that deletes the file once the stream is dropped.
+  fn open_temp_file(&self, file_path: impl AsRef<Path>) -> Result<FileStream, ErrBox>;
   fn write_file(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<(), ErrBox>;
+  /// Writes the file from the reader without buffering all its bytes in memory.
+  fn write_file_from_reader(&self, file_path: impl AsRef<Path>, reader: &mut dyn Read) -> Result<(), ErrBox>;
+  /// Appends the reader's bytes to the end of an existing file.
+  fn append_file_from_reader(&self, file_path: impl AsRef<Path>, reader: &mut dyn Read) -> Result<(), ErrBox>;
   fn write_file_text(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox>;
+  /// Renames the file, replacing any file at the new path.
+  fn rename_file(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn remove_dir_all(&self, dir_path: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn path_exists(&self, file_path: impl AsRef<Path>) -> bool;
+  /// Gets text that changes whenever the file is modified or None when it doesn't exist.
+  fn get_file_modified_stamp(&self, file_path: impl AsRef<Path>) -> Option<String>;
+  /// Gets the size and modified time of the file or None when it doesn't exist.
+  fn get_file_metadata(&self, file_path: impl AsRef<Path>) -> Option<FileMetadata>;
+  /// Gets the paths of the files in the directory.
+  fn read_dir(&self, dir_path: impl AsRef<Path>) -> Result<Vec<PathBuf>, ErrBox>;
   fn is_dir_empty(&self, dir_path: impl AsRef<Path>) -> Result<bool, ErrBox>;
   fn create_dir_all(&self, path: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn cwd(&self) -> PathBuf;
   fn log(&self, text: &str);
   fn log_stderr(&self, text: &str);
   fn fetch_url(&self, url: &Url) -> Result<Vec<u8>, ErrBox> {
+    self.fetch_url_stream(url)?.into_bytes()
+  }
+  /// Gets a stream of the url's bytes, which for remote urls is
+  /// downloaded to disk rather than into memory.
+  fn fetch_url_stream(&self, url: &Url) -> Result<FileStream, ErrBox> {
     if url.scheme() == "file" {
-      self.read_file(
+      self.open_file(
         &url
           .to_file_path()
           .map_err(|_| err_obj!("Error converting {} to a file path.", url.as_str()))?,
       )
     } else {
-      self.download_file(url.as_str())
+      let settings = self.get_settings();
+      if settings.offline() {
+        err!(
+          "Could not download {} because bvm is offline. Run `bvm config set offline false` to allow downloads.",
+          url.as_str()
+        )
+      } else {
+        download_file_with_mirrors(self, url.as_str(), settings.get_mirror_urls(url.as_str()))
+      }
     }
   }
-  fn download_file(&self, url: &str) -> Result<Vec<u8>, ErrBox>;
+  /// Downloads the url sending the headers only to the url's origin and
+  /// not to any other origin it redirects to.
+  fn download_file_with_headers(&self, url: &str, headers: &[(String, String)]) -> Result<FileStream, ErrBox>;
   fn log_action_with_progress<
     TResult: std::marker::Send + std::marker::Sync,
     TCreate: FnOnce(Box<dyn Fn(usize)>) -> TResult + std::marker::Send + std::marker::Sync,
@@ -49,11 +84,19 @@ pub trait Environment: Clone + std::marker::Send + std::marker::Sync + 'static {
   fn get_user_data_dir(&self) -> PathBuf {
     self.try_get_user_data_dir().unwrap()
   }
+  /// Attempts to get the bvm home directory.
+  #[cfg(windows)]
   fn try_get_user_home_dir(&self) -> Result<PathBuf, ErrBox>;
+  /// The directory bvm is installed in.
+  #[cfg(windows)]
   fn get_user_home_dir(&self) -> PathBuf {
     self.try_get_user_home_dir().unwrap()
   }
+  /// Gets the user's home directory, which is where files like .netrc are.
+  fn get_home_dir(&self) -> Option<PathBuf>;
   fn get_time_secs(&self) -> u64;
+  /// Blocks the current thread for the duration, such as between download attempts.
+  fn sleep(&self, duration: Duration);
   /// Gets the specified environment variable.
   fn get_env_var(&self, key: &str) -> Option<String>;
   /// Gets the environment path variable.
@@ -72,9 +115,27 @@ pub trait Environment: Clone + std::marker::Send + std::marker::Sync + 'static {
   fn set_env_variable(&self, key: String, value: String) -> Result<(), ErrBox>;
   #[cfg(windows)]
   fn remove_env_variable(&self, key: &str) -> Result<(), ErrBox>;
-  fn run_shell_command(&self, cwd: &Path, command: &str) -> Result<(), ErrBox>;
+  /// Runs the command with its output written to its log file, failing when
+  /// it exits with a non-zero code or runs longer than its timeout.
+  fn run_shell_command(&self, command: &ShellCommand) -> Result<(), ErrBox>;
+  /// Runs the executable in place of the current process on unix. Elsewhere
+  /// it waits for the executable to finish and returns its exit code.
+  fn exec_command(&self, command: &ExecCommand) -> Result<i32, ErrBox>;
+  /// Gets the path of the running bvm executable.
+  fn current_exe(&self) -> Result<PathBuf, ErrBox>;
+  /// Hard links the file to the new path, falling back to copying it.
+  fn hard_link_or_copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox>;
   fn exit(&self, code: i32) -> Result<(), ErrBox>;
   fn is_verbose(&self) -> bool;
+  /// Gets if the user can be prompted, which is when stdin and stderr are terminals.
+  fn is_interactive(&self) -> bool;
+  /// Asks the user a yes or no question, defaulting to no.
+  fn confirm(&self, prompt: &str) -> Result<bool, ErrBox>;
+  /// Gets the user's settings with any environment variable overrides applied.
+  ///
+  /// This is called on hot paths such as every download, so implementations
+  /// should load the settings once and cache them.
+  fn get_settings(&self) -> Settings;
 }
 
 // use a macro here so the expression provided is only evaluated when in verbose mode
@@ -87,3 +148,73 @@ macro_rules! log_verbose {
         }
     }
 }
+
+/// Downloads the url from its mirrors, falling back to each subsequent
+/// mirror on failure, or from the url itself when it has no mirrors.
+fn download_file_with_mirrors(
+  environment: &impl Environment,
+  url: &str,
+  mirror_urls: Vec<String>,
+) -> Result<FileStream, ErrBox> {
+  if mirror_urls.is_empty() {
+    return download_file_with_credentials(environment, url);
+  }
+
+  let mut errors = Vec::new();
+  for mirror_url in mirror_urls {
+    match download_file_with_credentials(environment, &mirror_url) {
+      Ok(stream) => {
+        log_verbose!(environment, "Downloaded {} from mirror {}", url, mirror_url);
+        return Ok(stream);
+      }
+      Err(err) => {
+        log_verbose!(
+          environment,
+          "Error downloading {} from mirror {}: {}",
+          url,
+          mirror_url,
+          err
+        );
+        errors.push(format!("{}: {}", mirror_url, err));
+      }
+    }
+  }
+  err!(
+    "Could not download {} from any of its mirrors.\n  {}",
+    url,
+    errors.join("\n  ")
+  )
+}
+
+/// Downloads the url sending any credentials for its host.
+///
+/// Credentials are not sent over http other than to the local machine.
+fn download_file_with_credentials(environment: &impl Environment, url: &str) -> Result<FileStream, ErrBox> {
+  let parsed_url = Url::parse(url).ok();
+  let credential = parsed_url
+    .as_ref()
+    .and_then(|url| get_credential_for_url(environment, url));
+  let headers = match credential {
+    Some(credential) => {
+      let parsed_url = parsed_url.as_ref().unwrap();
+      if parsed_url.scheme() == "http" && !is_loopback_host(parsed_url) {
+        return err!(
+          "Refusing to send the credentials for {} over an insecure connection. Use an https url instead.",
+          parsed_url.host_str().unwrap_or_default()
+        );
+      }
+      vec![("Authorization".to_string(), credential.get_authorization_header_value())]
+    }
+    None => Vec::new(),
+  };
+  environment.download_file_with_headers(url, &headers)
+}
+
+fn is_loopback_host(url: &Url) -> bool {
+  match url.host() {
+    Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+    Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+    Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+    None => false,
+  }
+}
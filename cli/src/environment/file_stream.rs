@@ -0,0 +1,79 @@
+use dprint_cli_core::types::ErrBox;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// A seekable stream of a file's bytes so that large downloads don't
+/// need to be held in memory.
+pub struct FileStream {
+  inner: Box<dyn ReadSeek + Send>,
+  len: u64,
+  /// A downloaded file that's deleted once the stream is dropped.
+  temp_file_path: Option<PathBuf>,
+}
+
+impl FileStream {
+  pub fn new(inner: impl ReadSeek + Send + 'static, len: u64) -> FileStream {
+    FileStream {
+      inner: Box::new(inner),
+      len,
+      temp_file_path: None,
+    }
+  }
+
+  #[cfg(test)]
+  pub fn from_bytes(bytes: Vec<u8>) -> FileStream {
+    let len = bytes.len() as u64;
+    FileStream::new(Cursor::new(bytes), len)
+  }
+
+  /// Creates a stream of a file that will be deleted when the stream is dropped.
+  pub fn from_temp_file(file_path: PathBuf) -> Result<FileStream, ErrBox> {
+    let file = std::fs::File::open(&file_path)?;
+    let len = file.metadata()?.len();
+    let mut stream = FileStream::new(file, len);
+    stream.temp_file_path = Some(file_path);
+    Ok(stream)
+  }
+
+  pub fn len(&self) -> u64 {
+    self.len
+  }
+
+  /// Seeks back to the start so the stream can be read again.
+  pub fn rewind(&mut self) -> Result<(), ErrBox> {
+    self.inner.seek(SeekFrom::Start(0))?;
+    Ok(())
+  }
+
+  pub fn into_bytes(mut self) -> Result<Vec<u8>, ErrBox> {
+    let mut bytes = Vec::with_capacity(self.len as usize);
+    self.read_to_end(&mut bytes)?;
+    Ok(bytes)
+  }
+}
+
+impl Read for FileStream {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    self.inner.read(buf)
+  }
+}
+
+impl Seek for FileStream {
+  fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    self.inner.seek(pos)
+  }
+}
+
+impl Drop for FileStream {
+  fn drop(&mut self) {
+    if let Some(file_path) = self.temp_file_path.take() {
+      // close the file before removing it for windows
+      self.inner = Box::new(Cursor::new(Vec::new()));
+      let _ = std::fs::remove_file(file_path);
+    }
+  }
+}
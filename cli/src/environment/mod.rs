@@ -2,12 +2,17 @@
 mod environment;
 
 mod common;
+mod download_url;
+mod file_stream;
 
 mod real_environment;
 #[cfg(test)]
 mod test_environment;
+#[cfg(test)]
+mod test_server;
 
 pub use environment::*;
+pub use file_stream::*;
 pub use real_environment::*;
 
 #[cfg(test)]
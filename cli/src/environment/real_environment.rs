@@ -1,30 +1,36 @@
-use dprint_cli_core::download_url;
 use dprint_cli_core::logging::{log_action_with_progress, Logger, ProgressBars};
 use dprint_cli_core::types::ErrBox;
 use std::env;
 use std::fs;
-use std::io::ErrorKind;
+use std::io::{ErrorKind, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Stdio};
-use std::time::SystemTime;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
-use super::Environment;
+use super::download_url::download_url;
+use super::{Environment, ExecCommand, FileMetadata, FileStream, ShellCommand};
+use crate::settings::{LogLevel, Settings};
 
 #[derive(Clone)]
 pub struct RealEnvironment {
   logger: Logger,
   progress_bars: Option<ProgressBars>,
   is_verbose: bool,
+  /// Loaded once so that settings aren't read from disk on every download.
+  settings: Settings,
 }
 
 impl RealEnvironment {
-  pub fn new(is_verbose: bool) -> Result<RealEnvironment, ErrBox> {
+  pub fn new() -> Result<RealEnvironment, ErrBox> {
     let logger = Logger::new("bvm", /* is silent */ false);
     let progress_bars = ProgressBars::new(&logger);
-    let environment = RealEnvironment {
+    let mut environment = RealEnvironment {
       logger,
       progress_bars,
-      is_verbose,
+      is_verbose: false,
+      settings: Settings::default(),
     };
 
     if let Ok(dir) = environment.try_get_local_user_data_dir() {
@@ -34,6 +40,13 @@ impl RealEnvironment {
       environment.create_dir_all(&dir)?;
     }
 
+    environment.settings = Settings::load(&environment);
+    match environment.settings.log_level() {
+      LogLevel::Quiet => environment.progress_bars = None,
+      LogLevel::Info => {}
+      LogLevel::Verbose => environment.is_verbose = true,
+    }
+
     Ok(environment)
   }
 }
@@ -60,6 +73,35 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn open_file(&self, file_path: impl AsRef<Path>) -> Result<FileStream, ErrBox> {
+    log_verbose!(self, "Opening file: {}", file_path.as_ref().display());
+    let open = || -> std::io::Result<FileStream> {
+      let file = fs::File::open(&file_path)?;
+      let len = file.metadata()?.len();
+      Ok(FileStream::new(file, len))
+    };
+    match open() {
+      Ok(stream) => Ok(stream),
+      Err(err) => err!(
+        "Error reading file {}: {}",
+        file_path.as_ref().display(),
+        err.to_string()
+      ),
+    }
+  }
+
+  fn open_temp_file(&self, file_path: impl AsRef<Path>) -> Result<FileStream, ErrBox> {
+    log_verbose!(self, "Opening file: {}", file_path.as_ref().display());
+    match FileStream::from_temp_file(file_path.as_ref().to_path_buf()) {
+      Ok(stream) => Ok(stream),
+      Err(err) => err!(
+        "Error reading file {}: {}",
+        file_path.as_ref().display(),
+        err.to_string()
+      ),
+    }
+  }
+
   fn write_file_text(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox> {
     self.write_file(file_path, file_text.as_bytes())
   }
@@ -76,6 +118,44 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn write_file_from_reader(&self, file_path: impl AsRef<Path>, reader: &mut dyn Read) -> Result<(), ErrBox> {
+    log_verbose!(self, "Writing file: {}", file_path.as_ref().display());
+    let result = fs::File::create(&file_path).and_then(|mut file| std::io::copy(reader, &mut file));
+    match result {
+      Ok(_) => Ok(()),
+      Err(err) => err!(
+        "Error writing file {}: {}",
+        file_path.as_ref().display(),
+        err.to_string()
+      ),
+    }
+  }
+
+  fn append_file_from_reader(&self, file_path: impl AsRef<Path>, reader: &mut dyn Read) -> Result<(), ErrBox> {
+    log_verbose!(self, "Appending to file: {}", file_path.as_ref().display());
+    let result = fs::OpenOptions::new()
+      .append(true)
+      .open(&file_path)
+      .and_then(|mut file| std::io::copy(reader, &mut file));
+    match result {
+      Ok(_) => Ok(()),
+      Err(err) => err!(
+        "Error writing file {}: {}",
+        file_path.as_ref().display(),
+        err.to_string()
+      ),
+    }
+  }
+
+  fn rename_file(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    log_verbose!(self, "Renaming {} to {}", from.display(), to.display());
+    match fs::rename(from, to) {
+      Ok(_) => Ok(()),
+      Err(err) => err!("Error renaming {} to {}: {}", from.display(), to.display(), err),
+    }
+  }
+
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<(), ErrBox> {
     log_verbose!(self, "Deleting file: {}", file_path.as_ref().display());
     match fs::remove_file(&file_path) {
@@ -106,9 +186,8 @@ impl Environment for RealEnvironment {
     }
   }
 
-  fn download_file(&self, url: &str) -> Result<Vec<u8>, ErrBox> {
-    log_verbose!(self, "Downloading url: {}", url);
-    download_url(url, &self.progress_bars, |key| self.get_env_var(key))
+  fn download_file_with_headers(&self, url: &str, headers: &[(String, String)]) -> Result<FileStream, ErrBox> {
+    download_url(self, url, headers, &self.progress_bars)
   }
 
   fn path_exists(&self, path: impl AsRef<Path>) -> bool {
@@ -116,6 +195,42 @@ impl Environment for RealEnvironment {
     path.as_ref().exists()
   }
 
+  fn get_file_modified_stamp(&self, file_path: impl AsRef<Path>) -> Option<String> {
+    let metadata = fs::metadata(file_path).ok()?;
+    let modified_time = metadata.modified().ok()?.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    // include the size for file systems with a coarse modified time
+    Some(format!("{}:{}", modified_time.as_nanos(), metadata.len()))
+  }
+
+  fn get_file_metadata(&self, file_path: impl AsRef<Path>) -> Option<FileMetadata> {
+    let metadata = fs::metadata(file_path).ok()?;
+    let modified_time = metadata.modified().ok()?.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    Some(FileMetadata {
+      len: metadata.len(),
+      modified_time_secs: modified_time.as_secs(),
+    })
+  }
+
+  fn read_dir(&self, dir_path: impl AsRef<Path>) -> Result<Vec<PathBuf>, ErrBox> {
+    log_verbose!(self, "Reading directory: {}", dir_path.as_ref().display());
+    let entries = match fs::read_dir(&dir_path) {
+      Ok(entries) => entries,
+      Err(err) => {
+        return err!(
+          "Error reading directory {}: {}",
+          dir_path.as_ref().display(),
+          err.to_string()
+        )
+      }
+    };
+    Ok(
+      entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect(),
+    )
+  }
+
   fn is_dir_empty(&self, dir_path: impl AsRef<Path>) -> Result<bool, ErrBox> {
     let mut result = match std::fs::read_dir(&dir_path) {
       Ok(result) => result,
@@ -175,7 +290,7 @@ impl Environment for RealEnvironment {
       let dir = dirs::data_local_dir().ok_or_else(|| err_obj!("Could not get user's local dir."))?;
       Ok(dir.join("bvm"))
     } else {
-      get_home_dir()
+      get_bvm_home_dir()
     }
   }
 
@@ -187,19 +302,24 @@ impl Environment for RealEnvironment {
       let dir = dirs::data_dir().ok_or_else(|| err_obj!("Could not get user's data dir."))?;
       Ok(dir.join("bvm"))
     } else {
-      get_home_dir()
+      get_bvm_home_dir()
     }
   }
 
+  #[cfg(windows)]
   fn try_get_user_home_dir(&self) -> Result<PathBuf, ErrBox> {
     log_verbose!(self, "Getting user home directory.");
     if let Some(dir_path) = self.get_env_var("BVM_HOME_DIR") {
       Ok(PathBuf::from(dir_path))
     } else {
-      get_home_dir()
+      get_bvm_home_dir()
     }
   }
 
+  fn get_home_dir(&self) -> Option<PathBuf> {
+    dirs::home_dir()
+  }
+
   fn get_env_var(&self, key: &str) -> Option<String> {
     log_verbose!(self, "Getting the {} environment variable.", key);
 
@@ -296,21 +416,62 @@ impl Environment for RealEnvironment {
       .as_secs()
   }
 
-  fn run_shell_command(&self, cwd: &Path, command: &str) -> Result<(), ErrBox> {
+  fn sleep(&self, duration: Duration) {
+    std::thread::sleep(duration);
+  }
+
+  fn run_shell_command(&self, command: &ShellCommand) -> Result<(), ErrBox> {
+    log_verbose!(self, "Running shell command: {}", command.command);
+    #[cfg(unix)]
+    let mut process = Command::new("/bin/sh");
+    #[cfg(unix)]
+    process.arg("-c").arg(&command.command);
+    // run in a new process group so that the whole group may be killed on timeout
     #[cfg(unix)]
-    return finalize_and_run_command(cwd, Command::new("/bin/sh").arg("-c").arg(command));
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
 
     #[cfg(target_os = "windows")]
-    return finalize_and_run_command(cwd, Command::new("cmd").arg("/C").arg(command));
+    let mut process = Command::new("cmd");
+    #[cfg(target_os = "windows")]
+    process.arg("/C").arg(&command.command);
+
+    if let Some(parent) = command.log_file_path.parent() {
+      self.create_dir_all(parent)?;
+    }
+    let log_file = fs::File::create(&command.log_file_path)?;
+    let mut child = process
+      .envs(command.env_vars.iter().map(|(key, value)| (key, value)))
+      .stdin(Stdio::null())
+      .stdout(log_file.try_clone()?)
+      .stderr(log_file)
+      .current_dir(&command.cwd)
+      .spawn()?;
+    #[cfg(unix)]
+    let _forward_signals = ForwardSignals::new(child.id());
+    #[cfg(target_os = "windows")]
+    let job = ProcessJob::new(&child).ok();
 
-    fn finalize_and_run_command(cwd: &Path, command: &mut Command) -> Result<(), ErrBox> {
-      let status = command
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .current_dir(cwd)
-        .status()?;
-      handle_exit_status(status)
+    let start_time = Instant::now();
+    loop {
+      if let Some(status) = child.try_wait()? {
+        return handle_exit_status(status);
+      }
+      if let Some(timeout) = command.timeout {
+        if start_time.elapsed() >= timeout {
+          #[cfg(unix)]
+          unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+          }
+          #[cfg(target_os = "windows")]
+          if let Some(job) = &job {
+            job.terminate();
+          }
+          let _ignore = child.kill();
+          let _ignore = child.wait();
+          return err!("Timed out after {} seconds.", timeout.as_secs());
+        }
+      }
+      std::thread::sleep(Duration::from_millis(50));
     }
 
     fn handle_exit_status(exit_status: ExitStatus) -> Result<(), ErrBox> {
@@ -327,6 +488,52 @@ impl Environment for RealEnvironment {
     }
   }
 
+  fn exec_command(&self, command: &ExecCommand) -> Result<i32, ErrBox> {
+    log_verbose!(self, "Executing: {}", command.executable_path.display());
+    let mut process = Command::new(&command.executable_path);
+    process.args(&command.args);
+    for key in command.env_changes.removed.iter() {
+      process.env_remove(key);
+    }
+    for (key, value) in command.env_changes.set.iter() {
+      process.env(key, value);
+    }
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::process::CommandExt;
+      // only returns on failure
+      let err = process.arg0(&command.arg0).exec();
+      err!("Error executing {}: {}", command.executable_path.display(), err)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+      // let the child process handle ctrl+c and exit on its own
+      ignore_ctrl_c();
+      match process.status() {
+        Ok(status) => Ok(status.code().unwrap_or(1)),
+        Err(err) => err!("Error executing {}: {}", command.executable_path.display(), err),
+      }
+    }
+  }
+
+  fn current_exe(&self) -> Result<PathBuf, ErrBox> {
+    Ok(env::current_exe()?)
+  }
+
+  fn hard_link_or_copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    log_verbose!(self, "Linking {} to {}", from.display(), to.display());
+    let _ = fs::remove_file(to);
+    if fs::hard_link(from, to).is_err() {
+      if let Err(err) = fs::copy(from, to) {
+        return err!("Error copying {} to {}: {}", from.display(), to.display(), err);
+      }
+    }
+    Ok(())
+  }
+
   fn exit(&self, code: i32) -> Result<(), ErrBox> {
     std::process::exit(code)
   }
@@ -334,9 +541,231 @@ impl Environment for RealEnvironment {
   fn is_verbose(&self) -> bool {
     self.is_verbose
   }
+
+  fn get_settings(&self) -> Settings {
+    self.settings.clone()
+  }
+
+  fn is_interactive(&self) -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+  }
+
+  fn confirm(&self, prompt: &str) -> Result<bool, ErrBox> {
+    let mut stderr = std::io::stderr();
+    write!(stderr, "{} [y/N] ", prompt)?;
+    stderr.flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn ignore_ctrl_c() {
+  use winapi::shared::minwindef::{BOOL, DWORD, TRUE};
+  use winapi::um::consoleapi::SetConsoleCtrlHandler;
+
+  // a handler is used instead of ignoring ctrl+c because ignoring is inherited by child processes
+  unsafe extern "system" fn handler(_: DWORD) -> BOOL {
+    TRUE
+  }
+  unsafe {
+    SetConsoleCtrlHandler(Some(handler), TRUE);
+  }
+}
+
+#[cfg(unix)]
+static SCRIPT_PROCESS_GROUP: AtomicI32 = AtomicI32::new(0);
+
+/// Forwards the signals that stop bvm to the process group of a script while
+/// it runs. The script is in its own process group, so it doesn't receive the
+/// signal the terminal sends on ctrl+c and would otherwise keep running.
+#[cfg(unix)]
+struct ForwardSignals {
+  previous_handlers: Vec<(libc::c_int, libc::sighandler_t)>,
 }
 
-fn get_home_dir() -> Result<PathBuf, ErrBox> {
+#[cfg(unix)]
+impl ForwardSignals {
+  pub fn new(process_group: u32) -> ForwardSignals {
+    extern "C" fn handler(signal: libc::c_int) {
+      let process_group = SCRIPT_PROCESS_GROUP.load(Ordering::SeqCst);
+      if process_group > 0 {
+        unsafe {
+          libc::kill(-process_group, signal);
+        }
+      }
+    }
+
+    SCRIPT_PROCESS_GROUP.store(process_group as libc::pid_t, Ordering::SeqCst);
+    let mut previous_handlers = Vec::new();
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+      let previous_handler =
+        unsafe { libc::signal(signal, handler as extern "C" fn(libc::c_int) as libc::sighandler_t) };
+      // keep ignoring signals that were ignored (ex. by nohup)
+      if previous_handler == libc::SIG_IGN {
+        unsafe {
+          libc::signal(signal, libc::SIG_IGN);
+        }
+      }
+      previous_handlers.push((signal, previous_handler));
+    }
+    ForwardSignals { previous_handlers }
+  }
+}
+
+#[cfg(unix)]
+impl Drop for ForwardSignals {
+  fn drop(&mut self) {
+    for (signal, previous_handler) in self.previous_handlers.iter() {
+      unsafe {
+        libc::signal(*signal, *previous_handler);
+      }
+    }
+    SCRIPT_PROCESS_GROUP.store(0, Ordering::SeqCst);
+  }
+}
+
+/// A job object containing a script's process tree, which is killed when the
+/// job is terminated or closed (including when bvm exits). Killing cmd.exe on
+/// its own would leave the processes it started running.
+#[cfg(target_os = "windows")]
+struct ProcessJob(winapi::um::winnt::HANDLE);
+
+#[cfg(target_os = "windows")]
+impl ProcessJob {
+  pub fn new(child: &std::process::Child) -> Result<ProcessJob, ErrBox> {
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject};
+    use winapi::um::winnt::{
+      JobObjectExtendedLimitInformation, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    unsafe {
+      let handle = CreateJobObjectW(std::ptr::null_mut(), std::ptr::null());
+      if handle.is_null() {
+        return err!("Error creating job object: {}", std::io::Error::last_os_error());
+      }
+      let job = ProcessJob(handle);
+      let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+      info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+      let is_set = SetInformationJobObject(
+        job.0,
+        JobObjectExtendedLimitInformation,
+        &mut info as *mut _ as *mut _,
+        std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+      );
+      if is_set == 0 || AssignProcessToJobObject(job.0, child.as_raw_handle() as _) == 0 {
+        return err!(
+          "Error assigning the process to a job object: {}",
+          std::io::Error::last_os_error()
+        );
+      }
+      Ok(job)
+    }
+  }
+
+  pub fn terminate(&self) {
+    unsafe {
+      winapi::um::jobapi2::TerminateJobObject(self.0, 1);
+    }
+  }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for ProcessJob {
+  fn drop(&mut self) {
+    unsafe {
+      winapi::um::handleapi::CloseHandle(self.0);
+    }
+  }
+}
+
+fn get_bvm_home_dir() -> Result<PathBuf, ErrBox> {
   let dir = dirs::home_dir().ok_or_else(|| err_obj!("Could not get user's home directory."))?;
   Ok(dir.join(".bvm"))
 }
+
+#[cfg(all(test, unix))]
+mod test {
+  use super::*;
+  use std::thread;
+
+  fn create_environment() -> RealEnvironment {
+    RealEnvironment {
+      logger: Logger::new("bvm", /* is silent */ true),
+      progress_bars: None,
+      is_verbose: false,
+      settings: Settings::default(),
+    }
+  }
+
+  fn create_temp_dir(name: &str) -> PathBuf {
+    let dir_path = env::temp_dir().join(format!("bvm-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir_path);
+    fs::create_dir_all(&dir_path).unwrap();
+    dir_path
+  }
+
+  fn is_process_running(pid: libc::pid_t) -> bool {
+    // a killed process may not be reaped in a container without an init process
+    let is_zombie = fs::read_to_string(format!("/proc/{}/stat", pid))
+      .map(|text| text.split_whitespace().nth(2) == Some("Z"))
+      .unwrap_or(false);
+    let is_alive = unsafe { libc::kill(pid, 0) == 0 };
+    is_alive && !is_zombie
+  }
+
+  #[test]
+  fn run_shell_command_kills_script_process_group() {
+    let dir_path = create_temp_dir("script-timeout");
+    let result = create_environment().run_shell_command(&ShellCommand {
+      cwd: dir_path.clone(),
+      command: "sleep 30 & echo $! > child.pid; wait".to_string(),
+      env_vars: Vec::new(),
+      log_file_path: dir_path.join("log.txt"),
+      timeout: Some(Duration::from_secs(1)),
+    });
+    assert_eq!(result.err().unwrap().to_string(), "Timed out after 1 seconds.");
+    let pid = fs::read_to_string(dir_path.join("child.pid"))
+      .unwrap()
+      .trim()
+      .parse()
+      .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert!(!is_process_running(pid));
+    fs::remove_dir_all(&dir_path).unwrap();
+
+    // signal handlers are process wide, so test forwarding in the same test
+    let dir_path = create_temp_dir("script-signal");
+    let log_file_path = dir_path.join("log.txt");
+    let handle = thread::spawn({
+      let dir_path = dir_path.clone();
+      let log_file_path = log_file_path.clone();
+      move || {
+        create_environment()
+          .run_shell_command(&ShellCommand {
+            cwd: dir_path,
+            command: "echo started; sleep 30".to_string(),
+            env_vars: Vec::new(),
+            log_file_path,
+            timeout: None,
+          })
+          .map_err(|err| err.to_string())
+      }
+    });
+    let start_time = Instant::now();
+    while fs::read_to_string(&log_file_path).unwrap_or_default().is_empty() {
+      assert!(start_time.elapsed() < Duration::from_secs(10));
+      thread::sleep(Duration::from_millis(10));
+    }
+
+    // bvm keeps running and the script receives the signal
+    unsafe {
+      libc::kill(libc::getpid(), libc::SIGTERM);
+    }
+    assert_eq!(handle.join().unwrap(), Err("Process terminated by signal.".to_string()));
+    assert!(start_time.elapsed() < Duration::from_secs(10));
+    fs::remove_dir_all(&dir_path).unwrap();
+  }
+}
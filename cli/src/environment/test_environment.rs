@@ -1,28 +1,49 @@
 use dprint_cli_core::types::ErrBox;
 use path_clean::PathClean;
 use std::collections::HashMap;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::environment::SYS_PATH_DELIMITER;
+use crate::settings::Settings;
 
-use super::Environment;
+use super::{Environment, ExecCommand, FileMetadata, FileStream, ShellCommand};
+
+type Headers = Vec<(String, String)>;
 
 #[derive(Clone)]
 pub struct TestEnvironment {
   // todo: single arc and mutex...
   is_verbose: Arc<Mutex<bool>>,
+  /// Answers to give to confirm prompts in order, which makes the environment interactive.
+  confirm_answers: Arc<Mutex<Vec<bool>>>,
   cwd: Arc<Mutex<String>>,
   files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+  /// Number of times each file was written, which stands in for its modified time.
+  file_write_counts: Arc<Mutex<HashMap<PathBuf, u64>>>,
+  /// Modified times of files that were set to be older than the current time.
+  file_modified_times: Arc<Mutex<HashMap<PathBuf, u64>>>,
+  sleeps: Arc<Mutex<Vec<Duration>>>,
   logged_messages: Arc<Mutex<Vec<String>>>,
   logged_errors: Arc<Mutex<Vec<String>>>,
-  run_shell_commands: Arc<Mutex<Vec<(String, String)>>>,
+  run_shell_commands: Arc<Mutex<Vec<ShellCommand>>>,
+  /// Output of shell commands that should fail keyed by the command.
+  shell_command_failures: Arc<Mutex<HashMap<String, String>>>,
+  exec_commands: Arc<Mutex<Vec<ExecCommand>>>,
+  exec_exit_code: Arc<Mutex<i32>>,
   remote_files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+  /// Headers sent in the last download of each url.
+  download_headers: Arc<Mutex<HashMap<String, Headers>>>,
+  download_counts: Arc<Mutex<HashMap<String, usize>>>,
   deleted_directories: Arc<Mutex<Vec<PathBuf>>>,
   path_dirs: Arc<Mutex<Vec<PathBuf>>>,
   #[cfg(target_os = "windows")]
   sys_env_variables: Arc<Mutex<HashMap<String, String>>>,
   env_variables: Arc<Mutex<HashMap<String, String>>>,
+  /// Settings loaded since the files or environment variables last changed.
+  settings: Arc<Mutex<Option<Settings>>>,
 }
 
 impl TestEnvironment {
@@ -34,19 +55,30 @@ impl TestEnvironment {
     files.insert(PathBuf::from("/bin/bvm.cmd"), Vec::new());
     files.insert(PathBuf::from("/bin/bvm.ps1"), Vec::new());
     files.insert(PathBuf::from("/bin/bvm.sh"), Vec::new());
+    files.insert(PathBuf::from("/bin/bvm-bin"), Vec::new());
     TestEnvironment {
       is_verbose: Arc::new(Mutex::new(false)),
+      confirm_answers: Arc::new(Mutex::new(Vec::new())),
       cwd: Arc::new(Mutex::new(String::from("/"))),
       files: Arc::new(Mutex::new(files)),
+      file_write_counts: Arc::new(Mutex::new(HashMap::new())),
+      file_modified_times: Arc::new(Mutex::new(HashMap::new())),
+      sleeps: Arc::new(Mutex::new(Vec::new())),
       logged_messages: Arc::new(Mutex::new(Vec::new())),
       logged_errors: Arc::new(Mutex::new(Vec::new())),
       run_shell_commands: Arc::new(Mutex::new(Vec::new())),
+      shell_command_failures: Arc::new(Mutex::new(HashMap::new())),
+      exec_commands: Arc::new(Mutex::new(Vec::new())),
+      exec_exit_code: Arc::new(Mutex::new(0)),
       remote_files: Arc::new(Mutex::new(HashMap::new())),
+      download_headers: Arc::new(Mutex::new(HashMap::new())),
+      download_counts: Arc::new(Mutex::new(HashMap::new())),
       deleted_directories: Arc::new(Mutex::new(Vec::new())),
       path_dirs: Arc::new(Mutex::new(vec![PathBuf::from("/data/shims"), PathBuf::from("/bin")])),
       #[cfg(target_os = "windows")]
       sys_env_variables: Arc::new(Mutex::new(HashMap::new())),
       env_variables: Arc::new(Mutex::new(env_variables)),
+      settings: Arc::new(Mutex::new(None)),
     }
   }
 
@@ -64,9 +96,32 @@ impl TestEnvironment {
   }
 
   pub fn take_run_shell_commands(&self) -> Vec<(String, String)> {
+    self
+      .take_run_shell_command_details()
+      .into_iter()
+      .map(|command| (command.cwd.to_string_lossy().to_string(), command.command))
+      .collect()
+  }
+
+  pub fn take_run_shell_command_details(&self) -> Vec<ShellCommand> {
     self.run_shell_commands.lock().unwrap().drain(..).collect()
   }
 
+  /// Makes the shell command fail after writing the output to its log file.
+  pub fn add_shell_command_failure(&self, command: &str, output: &str) {
+    let mut shell_command_failures = self.shell_command_failures.lock().unwrap();
+    shell_command_failures.insert(command.to_string(), output.to_string());
+  }
+
+  pub fn take_exec_commands(&self) -> Vec<ExecCommand> {
+    self.exec_commands.lock().unwrap().drain(..).collect()
+  }
+
+  /// Sets the exit code of the executables run by `exec_command`.
+  pub fn set_exec_exit_code(&self, code: i32) {
+    *self.exec_exit_code.lock().unwrap() = code;
+  }
+
   pub fn get_sys_env_variables(&self) -> Vec<(String, String)> {
     #[cfg(target_os = "windows")]
     let mut items = self
@@ -87,6 +142,33 @@ impl TestEnvironment {
     remote_files.insert(path.as_ref().to_string(), bytes);
   }
 
+  /// Gets the headers sent in the last download of the url.
+  pub fn get_download_headers(&self, url: &str) -> Option<Headers> {
+    self.download_headers.lock().unwrap().get(url).cloned()
+  }
+
+  /// Gets how many times the url was downloaded.
+  pub fn get_download_count(&self, url: &str) -> usize {
+    self.download_counts.lock().unwrap().get(url).copied().unwrap_or(0)
+  }
+
+  pub fn download_file(&self, url: &str) -> Result<Vec<u8>, ErrBox> {
+    self.download_file_with_headers(url, &[])?.into_bytes()
+  }
+
+  pub fn get_file_paths(&self) -> Vec<PathBuf> {
+    self.files.lock().unwrap().keys().cloned().collect()
+  }
+
+  pub fn set_file_modified_time(&self, file_path: impl AsRef<Path>, time_secs: u64) {
+    let file_path = file_path.as_ref().to_path_buf().clean();
+    self.file_modified_times.lock().unwrap().insert(file_path, time_secs);
+  }
+
+  pub fn take_sleeps(&self) -> Vec<Duration> {
+    self.sleeps.lock().unwrap().drain(..).collect()
+  }
+
   pub fn is_dir_deleted(&self, path: &Path) -> bool {
     let deleted_directories = self.deleted_directories.lock().unwrap();
     deleted_directories.contains(&path.to_path_buf())
@@ -102,6 +184,10 @@ impl TestEnvironment {
     *is_verbose = value;
   }
 
+  pub fn add_confirm_answer(&self, answer: bool) {
+    self.confirm_answers.lock().unwrap().push(answer);
+  }
+
   pub fn add_path_dir(&self, dir: impl AsRef<Path>) {
     let mut path_dirs = self.path_dirs.lock().unwrap();
     path_dirs.push(dir.as_ref().to_path_buf());
@@ -120,11 +206,17 @@ impl TestEnvironment {
   pub fn set_env_var(&self, key: impl AsRef<str>, value: impl AsRef<str>) {
     let mut env_variables = self.env_variables.lock().unwrap();
     env_variables.insert(key.as_ref().to_string(), value.as_ref().to_string());
+    self.clear_settings();
   }
 
   pub fn remove_env_var(&self, key: impl AsRef<str>) {
     let mut env_variables = self.env_variables.lock().unwrap();
     env_variables.remove(&key.as_ref().to_string());
+    self.clear_settings();
+  }
+
+  fn clear_settings(&self) {
+    self.settings.lock().unwrap().take();
   }
 }
 
@@ -144,7 +236,7 @@ impl Drop for TestEnvironment {
         "should not have logged errors left on drop"
       );
       assert_eq!(
-        self.run_shell_commands.lock().unwrap().clone(),
+        self.take_run_shell_commands(),
         Vec::<(String, String)>::new(),
         "should not have run shell commands left on drop"
       );
@@ -172,19 +264,59 @@ impl Environment for TestEnvironment {
     }
   }
 
+  fn open_file(&self, file_path: impl AsRef<Path>) -> Result<FileStream, ErrBox> {
+    Ok(FileStream::from_bytes(self.read_file(file_path)?))
+  }
+
+  fn open_temp_file(&self, file_path: impl AsRef<Path>) -> Result<FileStream, ErrBox> {
+    let stream = self.open_file(&file_path)?;
+    self.remove_file(file_path)?;
+    Ok(stream)
+  }
+
   fn write_file_text(&self, file_path: impl AsRef<Path>, file_text: &str) -> Result<(), ErrBox> {
     self.write_file(file_path, file_text.as_bytes())
   }
 
   fn write_file(&self, file_path: impl AsRef<Path>, bytes: &[u8]) -> Result<(), ErrBox> {
     let mut files = self.files.lock().unwrap();
-    files.insert(file_path.as_ref().to_path_buf().clean(), Vec::from(bytes));
+    let file_path = file_path.as_ref().to_path_buf().clean();
+    let mut file_write_counts = self.file_write_counts.lock().unwrap();
+    *file_write_counts.entry(file_path.clone()).or_insert(0) += 1;
+    self.file_modified_times.lock().unwrap().remove(&file_path);
+    files.insert(file_path, Vec::from(bytes));
+    self.clear_settings();
+    Ok(())
+  }
+
+  fn write_file_from_reader(&self, file_path: impl AsRef<Path>, reader: &mut dyn Read) -> Result<(), ErrBox> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    self.write_file(file_path, &bytes)
+  }
+
+  fn append_file_from_reader(&self, file_path: impl AsRef<Path>, reader: &mut dyn Read) -> Result<(), ErrBox> {
+    let mut bytes = self.read_file(&file_path)?;
+    reader.read_to_end(&mut bytes)?;
+    self.write_file(file_path, &bytes)
+  }
+
+  fn rename_file(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox> {
+    let bytes = self.read_file(&from)?;
+    let from = from.as_ref().to_path_buf().clean();
+    let modified_time = self.file_modified_times.lock().unwrap().remove(&from);
+    self.remove_file(&from)?;
+    self.write_file(&to, &bytes)?;
+    if let Some(modified_time) = modified_time {
+      self.set_file_modified_time(to, modified_time);
+    }
     Ok(())
   }
 
   fn remove_file(&self, file_path: impl AsRef<Path>) -> Result<(), ErrBox> {
     let mut files = self.files.lock().unwrap();
     files.remove(&file_path.as_ref().to_path_buf().clean());
+    self.clear_settings();
     Ok(())
   }
 
@@ -204,13 +336,20 @@ impl Environment for TestEnvironment {
     for path in delete_paths {
       files.remove(&path);
     }
+    self.clear_settings();
     Ok(())
   }
 
-  fn download_file(&self, url: &str) -> Result<Vec<u8>, ErrBox> {
+  fn download_file_with_headers(&self, url: &str, headers: &[(String, String)]) -> Result<FileStream, ErrBox> {
+    self
+      .download_headers
+      .lock()
+      .unwrap()
+      .insert(url.to_string(), headers.to_vec());
+    *self.download_counts.lock().unwrap().entry(url.to_string()).or_insert(0) += 1;
     let remote_files = self.remote_files.lock().unwrap();
     match remote_files.get(&String::from(url)) {
-      Some(bytes) => Ok(bytes.clone()),
+      Some(bytes) => Ok(FileStream::from_bytes(bytes.clone())),
       None => err!("Could not find file at url {}", url),
     }
   }
@@ -220,6 +359,37 @@ impl Environment for TestEnvironment {
     files.contains_key(&file_path.as_ref().to_path_buf().clean())
   }
 
+  fn get_file_modified_stamp(&self, file_path: impl AsRef<Path>) -> Option<String> {
+    let file_path = file_path.as_ref().to_path_buf().clean();
+    let files = self.files.lock().unwrap();
+    let file = files.get(&file_path)?;
+    let file_write_counts = self.file_write_counts.lock().unwrap();
+    let write_count = file_write_counts.get(&file_path).copied().unwrap_or(0);
+    Some(format!("{}:{}", write_count, file.len()))
+  }
+
+  fn get_file_metadata(&self, file_path: impl AsRef<Path>) -> Option<FileMetadata> {
+    let file_path = file_path.as_ref().to_path_buf().clean();
+    let len = self.files.lock().unwrap().get(&file_path)?.len() as u64;
+    let modified_time_secs = self.file_modified_times.lock().unwrap().get(&file_path).copied();
+    Some(FileMetadata {
+      len,
+      modified_time_secs: modified_time_secs.unwrap_or_else(|| self.get_time_secs()),
+    })
+  }
+
+  fn read_dir(&self, dir_path: impl AsRef<Path>) -> Result<Vec<PathBuf>, ErrBox> {
+    let dir_path = dir_path.as_ref().to_path_buf().clean();
+    let files = self.files.lock().unwrap();
+    let mut paths = files
+      .keys()
+      .filter(|file_path| file_path.parent() == Some(dir_path.as_path()))
+      .cloned()
+      .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+  }
+
   fn create_dir_all(&self, _: impl AsRef<Path>) -> Result<(), ErrBox> {
     Ok(())
   }
@@ -286,10 +456,21 @@ impl Environment for TestEnvironment {
     Ok(())
   }
 
-  fn run_shell_command(&self, cwd: &Path, command: &str) -> Result<(), ErrBox> {
-    let mut run_shell_commands = self.run_shell_commands.lock().unwrap();
-    run_shell_commands.push((cwd.to_string_lossy().to_string(), command.to_string()));
-    Ok(())
+  fn run_shell_command(&self, command: &ShellCommand) -> Result<(), ErrBox> {
+    self.run_shell_commands.lock().unwrap().push(command.clone());
+    let failure_output = self
+      .shell_command_failures
+      .lock()
+      .unwrap()
+      .get(&command.command)
+      .cloned();
+    match failure_output {
+      Some(output) => {
+        self.write_file_text(&command.log_file_path, &output)?;
+        err!("Received non zero exit code from shell command: 1")
+      }
+      None => self.write_file_text(&command.log_file_path, ""),
+    }
   }
 
   fn cwd(&self) -> PathBuf {
@@ -326,14 +507,37 @@ impl Environment for TestEnvironment {
     Ok(PathBuf::from("/data"))
   }
 
+  #[cfg(windows)]
   fn try_get_user_home_dir(&self) -> Result<PathBuf, ErrBox> {
     Ok(PathBuf::from("/.bvm"))
   }
 
+  fn get_home_dir(&self) -> Option<PathBuf> {
+    Some(PathBuf::from("/home"))
+  }
+
   fn get_time_secs(&self) -> u64 {
     123456
   }
 
+  fn sleep(&self, duration: Duration) {
+    self.sleeps.lock().unwrap().push(duration);
+  }
+
+  fn exec_command(&self, command: &ExecCommand) -> Result<i32, ErrBox> {
+    self.exec_commands.lock().unwrap().push(command.clone());
+    Ok(*self.exec_exit_code.lock().unwrap())
+  }
+
+  fn current_exe(&self) -> Result<PathBuf, ErrBox> {
+    Ok(PathBuf::from("/bin/bvm-bin"))
+  }
+
+  fn hard_link_or_copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<(), ErrBox> {
+    let bytes = self.read_file(from)?;
+    self.write_file(to, &bytes)
+  }
+
   fn exit(&self, code: i32) -> Result<(), ErrBox> {
     err!("Exited with code {}", code)
   }
@@ -341,4 +545,25 @@ impl Environment for TestEnvironment {
   fn is_verbose(&self) -> bool {
     *self.is_verbose.lock().unwrap()
   }
+
+  fn is_interactive(&self) -> bool {
+    !self.confirm_answers.lock().unwrap().is_empty()
+  }
+
+  fn confirm(&self, prompt: &str) -> Result<bool, ErrBox> {
+    let mut confirm_answers = self.confirm_answers.lock().unwrap();
+    assert!(!confirm_answers.is_empty(), "unexpected prompt: {}", prompt);
+    self.logged_errors.lock().unwrap().push(format!("{} [y/N]", prompt));
+    Ok(confirm_answers.remove(0))
+  }
+
+  fn get_settings(&self) -> Settings {
+    if let Some(settings) = self.settings.lock().unwrap().as_ref() {
+      return settings.clone();
+    }
+    // load outside the lock since loading reads files and may log
+    let settings = Settings::load(self);
+    *self.settings.lock().unwrap() = Some(settings.clone());
+    settings
+  }
 }
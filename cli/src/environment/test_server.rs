@@ -0,0 +1,161 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A request received by a `TestServer`.
+#[derive(Clone, Debug)]
+pub struct TestRequest {
+  pub path: String,
+  pub headers: Vec<(String, String)>,
+}
+
+impl TestRequest {
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+}
+
+/// A response for a `TestServer` to send.
+pub struct TestResponse {
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: Vec<u8>,
+  /// Closes the connection after sending this many bytes of the body.
+  pub truncate_at: Option<usize>,
+  /// Waits before sending the response.
+  pub delay: Option<Duration>,
+}
+
+impl TestResponse {
+  pub fn new(status: u16, body: impl Into<Vec<u8>>) -> TestResponse {
+    TestResponse {
+      status,
+      headers: Vec::new(),
+      body: body.into(),
+      truncate_at: None,
+      delay: None,
+    }
+  }
+
+  pub fn header(mut self, name: &str, value: impl AsRef<str>) -> TestResponse {
+    self.headers.push((name.to_string(), value.as_ref().to_string()));
+    self
+  }
+}
+
+/// An http server on a local port that responds to each request using the
+/// provided handler and records the requests it received.
+pub struct TestServer {
+  addr: SocketAddr,
+  requests: Arc<Mutex<Vec<TestRequest>>>,
+  is_stopped: Arc<AtomicBool>,
+}
+
+impl TestServer {
+  pub fn start(handler: impl Fn(&TestRequest) -> TestResponse + Send + Sync + 'static) -> TestServer {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let is_stopped = Arc::new(AtomicBool::new(false));
+    let handler = Arc::new(handler);
+
+    thread::spawn({
+      let requests = requests.clone();
+      let is_stopped = is_stopped.clone();
+      move || {
+        for stream in listener.incoming() {
+          if is_stopped.load(Ordering::SeqCst) {
+            break;
+          }
+          if let Ok(stream) = stream {
+            let requests = requests.clone();
+            let handler = handler.clone();
+            thread::spawn(move || handle_connection(stream, &requests, handler.as_ref()));
+          }
+        }
+      }
+    });
+
+    TestServer {
+      addr,
+      requests,
+      is_stopped,
+    }
+  }
+
+  pub fn url(&self, path: &str) -> String {
+    format!("http://{}{}", self.addr, path)
+  }
+
+  pub fn take_requests(&self) -> Vec<TestRequest> {
+    self.requests.lock().unwrap().drain(..).collect()
+  }
+}
+
+impl Drop for TestServer {
+  fn drop(&mut self) {
+    self.is_stopped.store(true, Ordering::SeqCst);
+    // wake up the listener so that its thread exits
+    let _ = TcpStream::connect(self.addr);
+  }
+}
+
+fn handle_connection(
+  mut stream: TcpStream,
+  requests: &Mutex<Vec<TestRequest>>,
+  handler: &(dyn Fn(&TestRequest) -> TestResponse + Send + Sync),
+) {
+  let request = match read_request(&stream) {
+    Some(request) => request,
+    None => return,
+  };
+  requests.lock().unwrap().push(request.clone());
+  let response = handler(&request);
+  if let Some(delay) = response.delay {
+    thread::sleep(delay);
+  }
+
+  let mut text = format!("HTTP/1.1 {} Test\r\n", response.status);
+  if !response
+    .headers
+    .iter()
+    .any(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+  {
+    text.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+  }
+  for (name, value) in response.headers.iter() {
+    text.push_str(&format!("{}: {}\r\n", name, value));
+  }
+  text.push_str("Connection: close\r\n\r\n");
+  let body_len = response.truncate_at.unwrap_or(response.body.len());
+  let _ = stream.write_all(text.as_bytes());
+  let _ = stream.write_all(&response.body[..body_len]);
+  let _ = stream.flush();
+}
+
+fn read_request(stream: &TcpStream) -> Option<TestRequest> {
+  let mut reader = BufReader::new(stream);
+  let mut line = String::new();
+  reader.read_line(&mut line).ok()?;
+  let path = line.split_whitespace().nth(1)?.to_string();
+  let mut headers = Vec::new();
+  loop {
+    line.clear();
+    reader.read_line(&mut line).ok()?;
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    if let Some(index) = line.find(':') {
+      headers.push((line[..index].to_string(), line[index + 1..].trim().to_string()));
+    }
+  }
+  Some(TestRequest { path, headers })
+}
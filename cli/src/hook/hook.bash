@@ -0,0 +1,13 @@
+if [ "$bvm_functions_protocol_version" != "__BVM_PROTOCOL_VERSION__" ]; then
+  echo "[bvm error]: The bvm hook requires the bvm shell functions for protocol version __BVM_PROTOCOL_VERSION__. Source an up to date bvm init script before the hook." >&2
+else
+  _bvm_hook() {
+    local previous_exit_status=$?
+    bvm_handle_env_messages "$(BVM_SHELL=sh "__BVM_BIN__" hidden hook-env)"
+    return $previous_exit_status
+  }
+
+  if [[ ";${PROMPT_COMMAND[*]:-};" != *";_bvm_hook;"* ]]; then
+    PROMPT_COMMAND="_bvm_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+  fi
+fi
@@ -0,0 +1,7 @@
+if test "$bvm_functions_protocol_version" != "__BVM_PROTOCOL_VERSION__"
+  echo "[bvm error]: The bvm hook requires the bvm shell functions for protocol version __BVM_PROTOCOL_VERSION__. Source an up to date bvm init script before the hook." >&2
+else
+  function __bvm_hook --on-event fish_prompt --on-variable PWD
+    bvm_handle_env_messages (string join \n -- (BVM_SHELL=fish "__BVM_BIN__" hidden hook-env))
+  end
+end
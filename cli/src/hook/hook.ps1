@@ -0,0 +1,32 @@
+function global:__bvm_set {
+  param([string]$name, [string]$value)
+  [Environment]::SetEnvironmentVariable($name, $value)
+}
+
+function global:__bvm_unset {
+  param([string]$name)
+  [Environment]::SetEnvironmentVariable($name, $null)
+}
+
+function global:__bvm_hook {
+  $previous_bvm_shell = $env:BVM_SHELL
+  $env:BVM_SHELL = "powershell"
+  try {
+    $messages = (& "__BVM_BIN__" hidden hook-env) | Out-String
+  } finally {
+    $env:BVM_SHELL = $previous_bvm_shell
+  }
+  if (-not [string]::IsNullOrWhiteSpace($messages)) {
+    Invoke-Expression $messages
+  }
+}
+
+if (-not (Test-Path function:global:__bvm_original_prompt)) {
+  Copy-Item function:global:prompt function:global:__bvm_original_prompt
+  function global:prompt {
+    $previous_exit_code = $global:LASTEXITCODE
+    __bvm_hook
+    $global:LASTEXITCODE = $previous_exit_code
+    __bvm_original_prompt
+  }
+}
@@ -0,0 +1,216 @@
+use dprint_cli_core::types::ErrBox;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::arg_parser::Shell;
+use crate::environment::{EnvChanges, Environment, SYS_PATH_DELIMITER};
+use crate::plugins::PluginsManifest;
+use crate::protocol::PROTOCOL_VERSION;
+
+const HOOK_STATE_ENV_VAR_NAME: &str = "BVM_HOOK_STATE";
+const BVM_BIN_PLACEHOLDER: &str = "__BVM_BIN__";
+const PROTOCOL_VERSION_PLACEHOLDER: &str = "__BVM_PROTOCOL_VERSION__";
+
+/// Gets the script that installs the hook in the provided shell.
+pub fn get_hook_script(environment: &impl Environment, shell: &Shell) -> Result<String, ErrBox> {
+  let script = match shell {
+    Shell::Bash => include_str!("hook.bash"),
+    Shell::Zsh => include_str!("hook.zsh"),
+    Shell::Fish => include_str!("hook.fish"),
+    Shell::Powershell => include_str!("hook.ps1"),
+  };
+  let bvm_bin = environment.current_exe()?;
+  Ok(
+    script
+      .trim_end()
+      .replace(BVM_BIN_PLACEHOLDER, &bvm_bin.to_string_lossy())
+      .replace(PROTOCOL_VERSION_PLACEHOLDER, &PROTOCOL_VERSION.to_string()),
+  )
+}
+
+/// The environment changes a project's binaries make relative to the global versions.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEnv {
+  set: Vec<(String, String)>,
+  removed: Vec<String>,
+  added_paths: Vec<String>,
+  removed_paths: Vec<String>,
+}
+
+impl ProjectEnv {
+  /// Creates the project environment from the pending changes of a manifest
+  /// where the project's binaries were switched to.
+  pub fn from_pending_env_changes(environment: &impl Environment, manifest: &PluginsManifest) -> ProjectEnv {
+    let added_env_vars = manifest.get_pending_added_env_variables(environment);
+    let mut removed = manifest
+      .get_pending_removed_env_variables(environment)
+      .into_keys()
+      .filter(|key| !added_env_vars.contains_key(key))
+      .collect::<Vec<_>>();
+    removed.sort();
+    let mut set = added_env_vars.into_iter().collect::<Vec<_>>();
+    set.sort();
+
+    ProjectEnv {
+      set,
+      removed,
+      added_paths: manifest.get_relative_pending_added_paths(environment),
+      removed_paths: manifest.get_relative_pending_removed_paths(environment),
+    }
+  }
+}
+
+/// What the hook applied to the shell, which is stored in an environment
+/// variable of the shell so that it can be reverted.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookState {
+  key: String,
+  project_env: ProjectEnv,
+  /// The values of the variables before the project's changes were applied.
+  previous_values: Vec<(String, Option<String>)>,
+}
+
+impl HookState {
+  pub fn from_env(environment: &impl Environment) -> Option<HookState> {
+    let text = environment.get_env_var(HOOK_STATE_ENV_VAR_NAME)?;
+    serde_json::from_str(&text).ok()
+  }
+
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+}
+
+/// Gets the changes to make to the shell in order to revert the previously
+/// applied project and apply the project of the current directory, if any.
+pub fn get_hook_env_changes(
+  environment: &impl Environment,
+  previous_state: Option<HookState>,
+  project: Option<(String, ProjectEnv)>,
+) -> EnvChanges {
+  let mut shell_env = ShellEnv::new(environment);
+
+  if let Some(state) = previous_state {
+    for (key, value) in state.previous_values {
+      shell_env.set(key, value);
+    }
+    shell_env.remove_paths(&state.project_env.added_paths);
+    shell_env.prepend_paths(&state.project_env.removed_paths);
+  }
+
+  if let Some((key, project_env)) = project {
+    let mut previous_values = Vec::new();
+    for (name, value) in project_env.set.iter() {
+      previous_values.push((name.clone(), shell_env.get(name)));
+      shell_env.set(name.clone(), Some(value.clone()));
+    }
+    for name in project_env.removed.iter() {
+      previous_values.push((name.clone(), shell_env.get(name)));
+      shell_env.set(name.clone(), None);
+    }
+    shell_env.remove_paths(&project_env.removed_paths);
+    shell_env.prepend_paths(&project_env.added_paths);
+
+    let state = HookState {
+      key,
+      project_env,
+      previous_values,
+    };
+    shell_env.set(
+      HOOK_STATE_ENV_VAR_NAME.to_string(),
+      Some(serde_json::to_string(&state).unwrap()),
+    );
+  } else {
+    shell_env.set(HOOK_STATE_ENV_VAR_NAME.to_string(), None);
+  }
+
+  shell_env.into_env_changes()
+}
+
+/// The shell's environment variables with changes applied.
+struct ShellEnv<'a, TEnvironment: Environment> {
+  environment: &'a TEnvironment,
+  changes: BTreeMap<String, Option<String>>,
+}
+
+impl<'a, TEnvironment: Environment> ShellEnv<'a, TEnvironment> {
+  fn new(environment: &'a TEnvironment) -> Self {
+    ShellEnv {
+      environment,
+      changes: BTreeMap::new(),
+    }
+  }
+
+  fn get(&self, name: &str) -> Option<String> {
+    match self.changes.get(name) {
+      Some(value) => value.clone(),
+      None => self.environment.get_env_var(name),
+    }
+  }
+
+  fn set(&mut self, name: String, value: Option<String>) {
+    self.changes.insert(name, value);
+  }
+
+  fn remove_paths(&mut self, paths: &[String]) {
+    let mut current_paths = self.get_paths();
+    let original_len = current_paths.len();
+    for path in paths {
+      if let Some(pos) = current_paths.iter().position(|p| p == path) {
+        current_paths.remove(pos);
+      }
+    }
+    if current_paths.len() != original_len {
+      self.set_paths(current_paths);
+    }
+  }
+
+  fn prepend_paths(&mut self, paths: &[String]) {
+    let mut current_paths = self.get_paths();
+    let original_len = current_paths.len();
+    for path in paths.iter().rev() {
+      if !current_paths.contains(path) {
+        current_paths.insert(0, path.clone());
+      }
+    }
+    if current_paths.len() != original_len {
+      self.set_paths(current_paths);
+    }
+  }
+
+  fn get_paths(&self) -> Vec<String> {
+    self
+      .get("PATH")
+      .unwrap_or_default()
+      .split(SYS_PATH_DELIMITER)
+      .filter(|p| !p.is_empty())
+      .map(String::from)
+      .collect()
+  }
+
+  fn set_paths(&mut self, paths: Vec<String>) {
+    self.set("PATH".to_string(), Some(paths.join(SYS_PATH_DELIMITER)));
+  }
+
+  fn into_env_changes(self) -> EnvChanges {
+    let mut env_changes = EnvChanges::default();
+    for (name, value) in self.changes {
+      let current_value = self.environment.get_env_var(&name);
+      match value {
+        Some(value) => {
+          if current_value.as_ref() != Some(&value) {
+            env_changes.set.push((name, value));
+          }
+        }
+        None => {
+          if current_value.is_some() {
+            env_changes.removed.push(name);
+          }
+        }
+      }
+    }
+    env_changes
+  }
+}
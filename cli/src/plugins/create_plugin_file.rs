@@ -0,0 +1,134 @@
+use dprint_cli_core::types::ErrBox;
+use url::Url;
+
+use super::{read_plugin_file, DownloadType, PlatformInfo, PlatformInfoCommand, PluginPlatform, SerializedPluginFile};
+use crate::environment::{Environment, FileStream};
+use crate::types::{BinaryName, CommandName, Version};
+use crate::utils::{self, parse_path_or_url_to_url, ArchiveFile, ChecksumUrl};
+
+/// Creates a plugin file by downloading and hashing the archive for each platform.
+pub fn create_plugin_file(
+  environment: &impl Environment,
+  binary_name: &BinaryName,
+  version: &Version,
+  description: &str,
+  platform_urls: &[(PluginPlatform, ChecksumUrl)],
+) -> Result<SerializedPluginFile, ErrBox> {
+  let mut plugin_file = SerializedPluginFile {
+    schema_version: 1,
+    name: binary_name.name.clone(),
+    owner: binary_name.owner.clone(),
+    version: version.clone(),
+    description: description.to_string(),
+    linux: None,
+    mac: None,
+    windows: None,
+    dependencies: Vec::new(),
+  };
+
+  for (platform, url) in platform_urls {
+    let mut stream = environment.fetch_url_stream(&url.url)?;
+    let checksum = match &url.checksum {
+      Some(checksum) => {
+        utils::verify_sha256_checksum_from_reader(&mut stream, checksum)?;
+        checksum.clone()
+      }
+      None => utils::get_sha256_checksum_from_reader(&mut stream)?,
+    };
+    let download_type = DownloadType::from_path(url.url.path());
+    let commands = match get_command_path(&binary_name.name, *platform, download_type, &mut stream)? {
+      Some(path) => vec![PlatformInfoCommand {
+        name: CommandName::from_string(binary_name.name.clone()),
+        path,
+      }],
+      None => {
+        environment.log_stderr(&format!(
+          "Could not find the '{}' executable in the {} archive. Run `bvm plugin contents {}` to choose a path for the command.",
+          binary_name.name,
+          platform.as_str(),
+          url.unresolved_path,
+        ));
+        Vec::new()
+      }
+    };
+
+    *plugin_file.get_platform_info_mut(*platform) = Some(PlatformInfo {
+      path: url.unresolved_path.clone(),
+      checksum,
+      download_type: download_type.as_str().to_string(),
+      output_dir: None,
+      commands,
+      on_pre_install: None,
+      on_post_install: None,
+      on_pre_uninstall: None,
+      on_post_uninstall: None,
+      environment: None,
+    });
+  }
+
+  Ok(plugin_file)
+}
+
+/// Downloads each platform's archive and updates its checksum. Returns the platforms that changed.
+pub fn update_plugin_file_checksums(
+  environment: &impl Environment,
+  plugin_file: &mut SerializedPluginFile,
+  base: &Url,
+) -> Result<Vec<PluginPlatform>, ErrBox> {
+  let mut changed_platforms = Vec::new();
+  for platform in PluginPlatform::all() {
+    if let Some(platform_info) = plugin_file.get_platform_info_mut(platform) {
+      let url = parse_path_or_url_to_url(&platform_info.path, base)?;
+      let checksum = utils::get_sha256_checksum_from_reader(&mut environment.fetch_url_stream(&url)?)?;
+      if platform_info.checksum != checksum {
+        platform_info.checksum = checksum;
+        changed_platforms.push(platform);
+      }
+    }
+  }
+  Ok(changed_platforms)
+}
+
+/// Serializes the plugin file, verifying it can be read back.
+pub fn serialize_plugin_file(plugin_file: &SerializedPluginFile) -> Result<String, ErrBox> {
+  let mut text = serde_json::to_string_pretty(plugin_file)?;
+  text.push('\n');
+  read_plugin_file(text.as_bytes())?;
+  Ok(text)
+}
+
+/// Gets the files within the archive.
+pub fn get_archive_files(download_type: DownloadType, stream: &mut FileStream) -> Result<Vec<ArchiveFile>, ErrBox> {
+  stream.rewind()?;
+  match download_type {
+    DownloadType::Zip => utils::list_zip_files(stream),
+    DownloadType::TarGz => utils::list_tar_gz_files(stream),
+    DownloadType::Binary => err!("Only zip and tar.gz archives have contents."),
+  }
+}
+
+fn get_command_path(
+  name: &str,
+  platform: PluginPlatform,
+  download_type: DownloadType,
+  stream: &mut FileStream,
+) -> Result<Option<String>, ErrBox> {
+  let executable_name = if platform == PluginPlatform::Windows {
+    format!("{}.exe", name)
+  } else {
+    name.to_string()
+  };
+
+  if download_type == DownloadType::Binary {
+    return Ok(Some(executable_name));
+  }
+
+  // use the least nested file with the executable name
+  let mut file_paths = get_archive_files(download_type, stream)?
+    .into_iter()
+    .map(|file| file.path)
+    .filter(|path| path.rsplit('/').next() == Some(executable_name.as_str()))
+    .collect::<Vec<_>>();
+  file_paths.sort_by_key(|path| path.matches('/').count());
+  Ok(file_paths.into_iter().next())
+}
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::path::PathBuf;
 
-use super::{get_plugin_dir, BinaryManifestItem, GlobalBinaryLocation, PluginsManifest};
+use super::{BinaryManifestItem, GlobalBinaryLocation, PluginsManifest};
 use crate::configuration::ConfigFileBinary;
 use crate::environment::{Environment, SYS_PATH_DELIMITER};
 use crate::types::{CommandName, NameSelector, PathOrVersionSelector, VersionSelector};
@@ -172,13 +172,11 @@ pub fn get_exec_binary_command_exe_path<TEnvironment: Environment>(
   let command = binary.commands.iter().filter(|c| &c.name == command_name).next();
 
   if let Some(command) = command {
-    Some(
-      get_plugin_dir(environment, &binary.name, &binary.version).join(if cfg!(windows) {
-        command.path.replace("/", "\\")
-      } else {
-        command.path.clone()
-      }),
-    )
+    Some(binary.get_binary_dir(environment).join(if cfg!(windows) {
+      command.path.replace("/", "\\")
+    } else {
+      command.path.clone()
+    }))
   } else {
     utils::get_command_executable_path_in_dirs(
       environment,
@@ -298,8 +296,8 @@ pub fn recreate_shim(
   plugin_manifest: &PluginsManifest,
   command_name: &CommandName,
 ) -> Result<(), ErrBox> {
-  if let Some(location) = get_command_exe_path(environment, &plugin_manifest, &command_name) {
-    crate::plugins::create_shim(environment, &command_name, &location)?;
+  if get_command_exe_path(environment, &plugin_manifest, &command_name).is_some() {
+    crate::plugins::create_shim(environment, &command_name)?;
   } else {
     environment.log_stderr(&format!(
       "Error creating shim for {}. Could not find binary path.",
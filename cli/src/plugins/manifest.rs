@@ -57,6 +57,11 @@ impl PendingEnvironmentChanges {
 pub struct BinaryManifestItemSource {
   pub path: String,
   pub checksum: String,
+  /// The download type of the path when it was installed directly from an
+  /// archive instead of from a binary manifest file. The archive's commands
+  /// are the binary's commands.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub download_type: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -71,6 +76,19 @@ pub struct BinaryManifestItem {
   pub source: BinaryManifestItemSource,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub environment: Option<BinaryEnvironment>,
+  /// Directory the binary was linked to via `bvm link` instead of being
+  /// extracted to the binaries directory.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub linked_dir: Option<PathBuf>,
+  /// Scripts from the binary manifest file stored at install time
+  /// so they can run when uninstalling without downloading it.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub on_pre_uninstall: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub on_post_uninstall: Option<String>,
+  /// The installed binaries that the binary's dependencies resolved to.
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub dependencies: Vec<BinaryIdentifier>,
 }
 
 impl BinaryManifestItem {
@@ -86,6 +104,14 @@ impl BinaryManifestItem {
     self.commands.iter().map(|c| c.name.clone()).collect()
   }
 
+  /// Gets the directory containing the binary's files.
+  pub fn get_binary_dir(&self, environment: &impl Environment) -> PathBuf {
+    match &self.linked_dir {
+      Some(linked_dir) => linked_dir.clone(),
+      None => get_plugin_dir(environment, &self.name, &self.version),
+    }
+  }
+
   pub fn get_env_paths(&self) -> Vec<String> {
     self
       .environment
@@ -96,7 +122,7 @@ impl BinaryManifestItem {
   }
 
   pub fn get_resolved_env_paths(&self, environment: &impl Environment) -> Vec<PathBuf> {
-    let bin_dir = get_plugin_dir(environment, &self.name, &self.version);
+    let bin_dir = self.get_binary_dir(environment);
     self
       .get_env_paths()
       .into_iter()
@@ -327,7 +353,7 @@ impl PluginsManifest {
     identifier: &BinaryIdentifier,
   ) -> HashMap<String, String> {
     if let Some(binary) = self.get_binary(&identifier) {
-      let bin_dir = get_plugin_dir(environment, &binary.name, &binary.version);
+      let bin_dir = binary.get_binary_dir(environment);
       binary
         .get_env_variables()
         .into_iter()
@@ -362,6 +388,48 @@ impl PluginsManifest {
 
   // binary
 
+  /// Gets the directories that the binary's dependencies and their dependencies
+  /// need on the path, which are the directories of their commands followed
+  /// by their environment paths. Uninstalled dependencies are skipped.
+  pub fn get_dependency_paths(&self, environment: &impl Environment, identifier: &BinaryIdentifier) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(identifier.clone());
+    let mut pending = match self.get_binary(identifier) {
+      Some(binary) => binary.dependencies.clone(),
+      None => Vec::new(),
+    };
+    while !pending.is_empty() {
+      let dependency = pending.remove(0);
+      // ignore cycles and binaries depended on more than once
+      if !visited.insert(dependency.clone()) {
+        continue;
+      }
+      if let Some(binary) = self.get_binary(&dependency) {
+        let binary_dir = binary.get_binary_dir(environment);
+        let command_dirs = binary
+          .commands
+          .iter()
+          .filter_map(|command| binary_dir.join(&command.path).parent().map(|dir| dir.to_path_buf()));
+        for path in command_dirs.chain(binary.get_resolved_env_paths(environment)) {
+          if !paths.contains(&path) {
+            paths.push(path);
+          }
+        }
+        pending.extend(binary.dependencies.iter().cloned());
+      }
+    }
+    paths
+  }
+
+  /// Gets the installed binaries that depend on the binary.
+  pub fn get_dependents(&self, identifier: &BinaryIdentifier) -> Vec<&BinaryManifestItem> {
+    self
+      .binaries()
+      .filter(|binary| binary.dependencies.contains(identifier))
+      .collect()
+  }
+
   pub fn get_binary(&self, identifier: &BinaryIdentifier) -> Option<&BinaryManifestItem> {
     self.binaries.get(identifier)
   }
@@ -478,7 +546,7 @@ impl PluginsManifest {
   }
 }
 
-pub(super) fn get_manifest_file_path(environment: &impl Environment) -> PathBuf {
+pub fn get_manifest_file_path(environment: &impl Environment) -> PathBuf {
   let user_data_dir = environment.get_user_data_dir(); // share across domains
   user_data_dir.join("binaries-manifest.json")
 }
@@ -1,12 +1,27 @@
+mod create_plugin_file;
 mod get_plugin_dir;
 pub mod helpers;
 mod manifest;
 mod plugins_mut;
+mod resolve_cache;
 mod serialized_plugin_file;
 mod setup;
+mod validate_plugin_file;
 
+pub use create_plugin_file::*;
 pub use get_plugin_dir::*;
 pub use manifest::*;
 pub use plugins_mut::*;
+pub use resolve_cache::*;
 pub use serialized_plugin_file::*;
 pub use setup::create_shim;
+pub use setup::download_archives;
+pub use setup::get_archive_plugin_file;
+pub use setup::get_installed_plugin_file;
+pub use setup::get_plugin_file;
+pub use setup::get_shim_command_name;
+pub use setup::read_plugin_file;
+pub use setup::verify_env_var_name;
+pub use setup::DownloadType;
+pub use setup::PluginFile;
+pub use validate_plugin_file::*;
@@ -1,26 +1,35 @@
 use dprint_cli_core::types::ErrBox;
+use std::path::Path;
 
+use super::clear_resolve_cache;
 use super::helpers;
 use super::manifest::get_manifest_file_path;
 use super::setup::get_plugin_file;
 use super::setup::get_shim_paths;
+use super::setup::link_binary;
 use super::setup::setup_plugin;
 use super::setup::PluginFile;
 use super::BinaryIdentifier;
 use super::BinaryManifestItem;
 use super::GlobalBinaryLocation;
+use super::PlatformInfoCommand;
+use super::PluginDependency;
 use super::PluginsManifest;
+use crate::arg_parser::parse_name_selector;
 use crate::configuration::ConfigFileBinary;
 use crate::environment::Environment;
+use crate::registry;
+use crate::scripts::{run_script, ScriptKind, ScriptTarget, ScriptsMode};
 use crate::types::BinaryName;
 use crate::types::CommandName;
+use crate::types::Version;
 use crate::types::VersionSelector;
 use crate::utils;
 use crate::utils::ChecksumUrl;
 
 pub enum UrlInstallAction {
   None,
-  Install(PluginFile),
+  Install(Box<PluginFile>),
 }
 
 /// Used to make changes to the plugins manifest.
@@ -58,8 +67,119 @@ impl<TEnvironment: Environment> PluginsMut<TEnvironment> {
 
   // general
 
-  pub fn setup_plugin<'a>(&'a mut self, plugin_file: &PluginFile) -> Result<&'a BinaryManifestItem, ErrBox> {
-    let item = setup_plugin(&self.environment, plugin_file)?;
+  /// Sets up the plugin after installing any of its dependencies that aren't installed.
+  pub fn setup_plugin<'a>(
+    &'a mut self,
+    plugin_file: &PluginFile,
+    scripts_mode: ScriptsMode,
+  ) -> Result<&'a BinaryManifestItem, ErrBox> {
+    self.setup_plugin_with_dependents(plugin_file, scripts_mode, &mut Vec::new())
+  }
+
+  /// The dependents are the binaries being set up that led to setting up this one.
+  fn setup_plugin_with_dependents<'a>(
+    &'a mut self,
+    plugin_file: &PluginFile,
+    scripts_mode: ScriptsMode,
+    dependents: &mut Vec<BinaryName>,
+  ) -> Result<&'a BinaryManifestItem, ErrBox> {
+    let binary_name = plugin_file.get_binary_name();
+    if dependents.contains(&binary_name) {
+      return err!(
+        "Found a dependency cycle: {} -> {}",
+        dependents
+          .iter()
+          .map(|name| name.to_string())
+          .collect::<Vec<_>>()
+          .join(" -> "),
+        binary_name
+      );
+    }
+
+    dependents.push(binary_name);
+    let mut dependencies = Vec::new();
+    for dependency in plugin_file.get_dependencies() {
+      match self.install_dependency(plugin_file, dependency, scripts_mode, dependents) {
+        Ok(identifier) => dependencies.push(identifier),
+        Err(err) => return err!("Error installing dependency '{}'. {}", dependency.name, err.to_string()),
+      }
+    }
+    dependents.pop();
+
+    let mut item = setup_plugin(&self.environment, plugin_file, scripts_mode)?;
+    item.dependencies = dependencies;
+    let identifier = item.get_identifier();
+    self.manifest.binaries.insert(identifier.clone(), item);
+    Ok(self.manifest.get_binary(&identifier).unwrap())
+  }
+
+  /// Installs the dependency unless a matching version is installed and gets the binary it resolved to.
+  fn install_dependency(
+    &mut self,
+    plugin_file: &PluginFile,
+    dependency: &PluginDependency,
+    scripts_mode: ScriptsMode,
+    dependents: &mut Vec<BinaryName>,
+  ) -> Result<BinaryIdentifier, ErrBox> {
+    let name_selector = parse_name_selector(dependency.name.clone());
+    let version_selector = match &dependency.version {
+      Some(version) => Some(VersionSelector::parse_for_config(version)?),
+      None => None,
+    };
+
+    let installed_binary = match &version_selector {
+      Some(version_selector) => {
+        helpers::get_latest_binary_matching_name_and_version(&self.manifest, &name_selector, version_selector)
+      }
+      None => helpers::get_latest_binary(&self.manifest.get_binaries_matching_name(&name_selector)),
+    };
+    if let Some(binary) = installed_binary {
+      return Ok(binary.get_identifier());
+    }
+
+    let url = match &dependency.url {
+      Some(url) => utils::parse_checksum_url(url, &plugin_file.url)?,
+      None => registry::resolve_binary_url(
+        &self.environment,
+        &name_selector,
+        version_selector.as_ref(),
+        dependency.registry.as_deref(),
+      )?,
+    };
+    let dependency_file = match self.get_url_install_action(&url, version_selector.as_ref(), false)? {
+      UrlInstallAction::Install(dependency_file) => dependency_file,
+      UrlInstallAction::None => match self.manifest.get_identifier_from_url(&url) {
+        Some(identifier) => return Ok(identifier.clone()),
+        None => return err!("Could not find the installed binary for {}.", url.url),
+      },
+    };
+    let dependency_name = dependency_file.get_binary_name();
+    if !name_selector.is_match(&dependency_name) {
+      return err!(
+        "Expected {} to be a binary manifest file for '{}', but it was for '{}'.",
+        url.url,
+        name_selector,
+        dependency_name
+      );
+    }
+
+    let item = self.setup_plugin_with_dependents(&dependency_file, scripts_mode, dependents)?;
+    let identifier = item.get_identifier();
+    for command_name in item.get_command_names() {
+      self.set_global_binary_if_not_set(&identifier, &command_name)?;
+    }
+    self.save()?; // write for every dependency in case the dependent fails
+    Ok(identifier)
+  }
+
+  pub fn link_binary<'a>(
+    &'a mut self,
+    dir_path: &Path,
+    binary_name: &BinaryName,
+    version: &Version,
+    commands: &Vec<PlatformInfoCommand>,
+  ) -> Result<&'a BinaryManifestItem, ErrBox> {
+    let item = link_binary(&self.environment, dir_path, binary_name, version, commands)?;
     let identifier = item.get_identifier();
     self.manifest.binaries.insert(identifier.clone(), item);
     Ok(self.manifest.get_binary(&identifier).unwrap())
@@ -73,9 +193,9 @@ impl<TEnvironment: Environment> PluginsMut<TEnvironment> {
   ) -> Result<UrlInstallAction, ErrBox> {
     // always install the url version for force
     if force_install {
-      return Ok(UrlInstallAction::Install(
+      return Ok(UrlInstallAction::Install(Box::new(
         self.get_and_associate_plugin_file(checksum_url)?,
-      ));
+      )));
     }
 
     // check the cache for if the identifier is saved
@@ -110,7 +230,7 @@ impl<TEnvironment: Environment> PluginsMut<TEnvironment> {
     }
 
     // install the specified url's plugin file
-    Ok(UrlInstallAction::Install(plugin_file))
+    Ok(UrlInstallAction::Install(Box::new(plugin_file)))
   }
 
   fn error_if_identifier_not_matches_version_selector(
@@ -192,13 +312,49 @@ impl<TEnvironment: Environment> PluginsMut<TEnvironment> {
 
   // binaries
 
-  pub fn remove_binary(&mut self, identifier: &BinaryIdentifier) -> Result<(), ErrBox> {
-    let previous_global_command_names = self.manifest.get_global_command_names(&identifier);
-    let binary_info = if let Some(item) = self.manifest.get_binary(identifier) {
-      Some((item.name.clone(), item.get_command_names()))
-    } else {
-      None
-    };
+  /// Removes the binary from the manifest along with any shims only it used
+  /// and deletes its directory, running its uninstall scripts before and after.
+  ///
+  /// The directory of a linked binary is not owned by bvm, so it's left on the disk.
+  pub fn remove_binary(&mut self, identifier: &BinaryIdentifier, skip_scripts: bool) -> Result<(), ErrBox> {
+    let item = self.manifest.get_binary(identifier).cloned();
+    if let Some(item) = &item {
+      self.run_pre_uninstall_script(item, skip_scripts)?;
+    }
+
+    self.remove_binary_from_manifest(identifier)?;
+
+    if let Some(item) = item {
+      if item.linked_dir.is_none() {
+        // save first so the manifest never refers to a deleted binary
+        self.save()?;
+        self.remove_binary_dir(&item)?;
+      }
+      // the binary is already removed at this point, so only warn when this fails
+      if let Err(err) = self.run_post_uninstall_script(&item, skip_scripts) {
+        self
+          .environment
+          .log_stderr(&format!("{} The binary was still removed.", err));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Removes the binary from the manifest along with any shims only it used
+  /// without running its uninstall scripts since it's about to be set up again.
+  ///
+  /// The binary's files are left on the disk for setting it up to replace.
+  pub fn remove_binary_for_reinstall(&mut self, identifier: &BinaryIdentifier) -> Result<(), ErrBox> {
+    self.remove_binary_from_manifest(identifier)
+  }
+
+  fn remove_binary_from_manifest(&mut self, identifier: &BinaryIdentifier) -> Result<(), ErrBox> {
+    let previous_global_command_names = self.manifest.get_global_command_names(identifier);
+    let binary_info = self
+      .manifest
+      .get_binary(identifier)
+      .map(|item| (item.name.clone(), item.get_command_names()));
 
     self.manifest.binaries.remove(identifier);
 
@@ -215,15 +371,81 @@ impl<TEnvironment: Environment> PluginsMut<TEnvironment> {
 
     // check if this is the last binary with this command. If so, delete the shim
     for command_name in previous_global_command_names.iter() {
-      if !self.manifest.has_binary_with_command(&command_name) {
-        for shim_path in get_shim_paths(&self.environment, &command_name) {
+      if !self.manifest.has_binary_with_command(command_name) {
+        for shim_path in get_shim_paths(&self.environment, command_name) {
           self.environment.remove_file(&shim_path)?;
         }
       }
     }
+
     Ok(())
   }
 
+  /// Deletes the binary's directory along with its parent directories when empty.
+  fn remove_binary_dir(&self, item: &BinaryManifestItem) -> Result<(), ErrBox> {
+    let plugin_dir = item.get_binary_dir(&self.environment);
+    self.environment.remove_dir_all(&plugin_dir)?;
+
+    let binary_name_dir = plugin_dir.parent().unwrap();
+    if self.environment.is_dir_empty(binary_name_dir)? {
+      self.environment.remove_dir_all(binary_name_dir)?;
+      // now delete the owner name if empty
+      let owner_name_dir = binary_name_dir.parent().unwrap();
+      if self.environment.is_dir_empty(owner_name_dir)? {
+        self.environment.remove_dir_all(owner_name_dir)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Runs the binary's onPreUninstall script in its directory if it has one.
+  fn run_pre_uninstall_script(&self, item: &BinaryManifestItem, skip_scripts: bool) -> Result<(), ErrBox> {
+    match &item.on_pre_uninstall {
+      Some(script) => {
+        let binary_dir = item.get_binary_dir(&self.environment);
+        self.run_uninstall_script(item, ScriptKind::PreUninstall, script, &binary_dir, skip_scripts)
+      }
+      None => Ok(()),
+    }
+  }
+
+  /// Runs the binary's onPostUninstall script if it has one. This is done after
+  /// the binary's directory is deleted, so it runs in the local user data directory.
+  fn run_post_uninstall_script(&self, item: &BinaryManifestItem, skip_scripts: bool) -> Result<(), ErrBox> {
+    match &item.on_post_uninstall {
+      Some(script) => {
+        let local_data_dir = self.environment.get_local_user_data_dir();
+        self.run_uninstall_script(item, ScriptKind::PostUninstall, script, &local_data_dir, skip_scripts)
+      }
+      None => Ok(()),
+    }
+  }
+
+  fn run_uninstall_script(
+    &self,
+    item: &BinaryManifestItem,
+    kind: ScriptKind,
+    script: &str,
+    cwd: &Path,
+    skip_scripts: bool,
+  ) -> Result<(), ErrBox> {
+    let binary_dir = item.get_binary_dir(&self.environment);
+    run_script(
+      &self.environment,
+      kind,
+      &item.source.path,
+      script,
+      cwd,
+      &ScriptTarget::Binary {
+        name: &item.name,
+        version: &item.version,
+        binary_dir: &binary_dir,
+        output_dir: None,
+      },
+      ScriptsMode::from_skip(skip_scripts),
+    )
+  }
+
   pub fn use_global_version(
     &mut self,
     command_name: &CommandName,
@@ -326,6 +548,7 @@ impl<TEnvironment: Environment> PluginsMut<TEnvironment> {
     let file_path = get_manifest_file_path(&self.environment);
     let serialized_manifest = serde_json::to_string(&self.manifest)?;
     self.environment.write_file_text(&file_path, &serialized_manifest)?;
+    clear_resolve_cache(&self.environment)?;
 
     Ok(())
   }
@@ -0,0 +1,93 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_cli_core::types::ErrBox;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::manifest::get_manifest_file_path;
+use crate::environment::{EnvChanges, Environment};
+use crate::types::CommandName;
+
+/// The executable a command resolved to along with the environment changes to apply.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedCommand {
+  pub executable_path: PathBuf,
+  pub env_changes: EnvChanges,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveCacheEntry {
+  key: String,
+  resolved_command: ResolvedCommand,
+}
+
+/// Gets the key for caching the command's resolution, which changes when the
+/// configuration file, binaries manifest, or anything else the resolution reads
+/// from the environment changes. Returns None when there's nothing installed to resolve.
+pub fn get_resolve_cache_key(
+  environment: &impl Environment,
+  config_file_path: Option<&Path>,
+  command_name: &CommandName,
+) -> Option<String> {
+  let manifest_file_path = get_manifest_file_path(environment);
+  let manifest_stamp = format!(
+    "{}@{}",
+    manifest_file_path.display(),
+    environment.get_file_modified_stamp(&manifest_file_path)?
+  );
+  let config_file_stamp = match config_file_path {
+    Some(config_file_path) => format!(
+      "{}@{}",
+      config_file_path.display(),
+      environment.get_file_modified_stamp(config_file_path)?
+    ),
+    // a configuration file may be created in the directory later
+    None => environment.cwd().display().to_string(),
+  };
+  // the env changes are based on the path and the binary directories are in the local data dir
+  Some(format!(
+    "{}\n{}\n{}\n{}\n{}",
+    command_name,
+    config_file_stamp,
+    manifest_stamp,
+    environment.get_local_user_data_dir().display(),
+    environment.get_env_path()
+  ))
+}
+
+pub fn get_cached_resolved_command(environment: &impl Environment, key: &str) -> Option<ResolvedCommand> {
+  let text = environment.read_file_text(get_cache_file_path(environment, key)).ok()?;
+  let entry = serde_json::from_str::<ResolveCacheEntry>(&text).ok()?;
+  // guard against hash collisions
+  if entry.key == key {
+    Some(entry.resolved_command)
+  } else {
+    None
+  }
+}
+
+pub fn cache_resolved_command(
+  environment: &impl Environment,
+  key: &str,
+  resolved_command: &ResolvedCommand,
+) -> Result<(), ErrBox> {
+  let entry = ResolveCacheEntry {
+    key: key.to_string(),
+    resolved_command: resolved_command.clone(),
+  };
+  environment.create_dir_all(get_cache_dir(environment))?;
+  environment.write_file_text(get_cache_file_path(environment, key), &serde_json::to_string(&entry)?)
+}
+
+pub fn clear_resolve_cache(environment: &impl Environment) -> Result<(), ErrBox> {
+  environment.remove_dir_all(get_cache_dir(environment))
+}
+
+fn get_cache_dir(environment: &impl Environment) -> PathBuf {
+  environment.get_local_user_data_dir().join("resolve-cache")
+}
+
+fn get_cache_file_path(environment: &impl Environment, key: &str) -> PathBuf {
+  get_cache_dir(environment).join(format!("{}.json", get_sha256_checksum(key.as_bytes())))
+}
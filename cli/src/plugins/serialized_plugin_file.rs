@@ -12,12 +12,82 @@ pub struct SerializedPluginFile {
   pub owner: String,
   pub version: Version,
   pub description: String,
-  #[serde(rename = "linux-x86_64")]
+  #[serde(rename = "linux-x86_64", skip_serializing_if = "Option::is_none")]
   pub linux: Option<PlatformInfo>,
-  #[serde(rename = "darwin-x86_64")]
+  #[serde(rename = "darwin-x86_64", skip_serializing_if = "Option::is_none")]
   pub mac: Option<PlatformInfo>,
-  #[serde(rename = "windows-x86_64")]
+  #[serde(rename = "windows-x86_64", skip_serializing_if = "Option::is_none")]
   pub windows: Option<PlatformInfo>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub dependencies: Vec<PluginDependency>,
+}
+
+impl SerializedPluginFile {
+  pub fn get_platform_info(&self, platform: PluginPlatform) -> &Option<PlatformInfo> {
+    match platform {
+      PluginPlatform::Linux => &self.linux,
+      PluginPlatform::Mac => &self.mac,
+      PluginPlatform::Windows => &self.windows,
+    }
+  }
+
+  pub fn get_platform_info_mut(&mut self, platform: PluginPlatform) -> &mut Option<PlatformInfo> {
+    match platform {
+      PluginPlatform::Linux => &mut self.linux,
+      PluginPlatform::Mac => &mut self.mac,
+      PluginPlatform::Windows => &mut self.windows,
+    }
+  }
+}
+
+/// Another binary that must be installed for the binary to work.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDependency {
+  /// Name selector of the binary (ex. `owner/name` or `name`).
+  pub name: String,
+  /// Version selector where `1` is equivalent to `^1`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub version: Option<String>,
+  /// Binary manifest file to install the dependency from.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+  /// Registry to find the dependency in instead of the user's registries.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub registry: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PluginPlatform {
+  Linux,
+  Mac,
+  Windows,
+}
+
+impl PluginPlatform {
+  pub fn all() -> [PluginPlatform; 3] {
+    [PluginPlatform::Linux, PluginPlatform::Mac, PluginPlatform::Windows]
+  }
+
+  /// The platform of the current machine.
+  pub fn current() -> PluginPlatform {
+    if cfg!(target_os = "windows") {
+      PluginPlatform::Windows
+    } else if cfg!(target_os = "macos") {
+      PluginPlatform::Mac
+    } else {
+      PluginPlatform::Linux
+    }
+  }
+
+  /// The key of the platform in the plugin file.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PluginPlatform::Linux => "linux-x86_64",
+      PluginPlatform::Mac => "darwin-x86_64",
+      PluginPlatform::Windows => "windows-x86_64",
+    }
+  }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -27,10 +97,18 @@ pub struct PlatformInfo {
   pub checksum: String,
   #[serde(rename = "type")]
   pub download_type: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub output_dir: Option<String>,
   pub commands: Vec<PlatformInfoCommand>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub on_pre_install: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub on_post_install: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub on_pre_uninstall: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub on_post_uninstall: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub environment: Option<BinaryEnvironment>,
 }
 
@@ -0,0 +1,32 @@
+use dprint_cli_core::types::ErrBox;
+use std::thread;
+
+use super::PluginFile;
+use crate::environment::Environment;
+
+/// Downloads the archives of the plugin files with at most the provided
+/// number of downloads happening at the same time.
+pub fn download_archives(
+  environment: &impl Environment,
+  plugin_files: &[&PluginFile],
+  parallelism: usize,
+) -> Result<(), ErrBox> {
+  for chunk in plugin_files.chunks(parallelism.max(1)) {
+    let results = thread::scope(|scope| {
+      let handles = chunk
+        .iter()
+        .map(|plugin_file| scope.spawn(move || plugin_file.download_archive(environment)))
+        .collect::<Vec<_>>();
+      handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Vec<_>>()
+    });
+    for (plugin_file, result) in chunk.iter().zip(results) {
+      if let Err(err) = result {
+        return err!("Error downloading {}. {}", plugin_file.display(), err.to_string());
+      }
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,65 @@
+use dprint_cli_core::types::ErrBox;
+use std::sync::Mutex;
+
+use super::{verify_plugin_binary_name, DownloadType, PluginFile};
+use crate::environment::Environment;
+use crate::plugins::{PlatformInfo, PlatformInfoCommand, PluginPlatform, SerializedPluginFile};
+use crate::types::{BinaryName, Version};
+use crate::utils::{get_sha256_checksum_from_reader, verify_sha256_checksum_from_reader, ChecksumUrl};
+
+/// Creates an in-memory plugin file for installing an archive on the current
+/// platform without a binary manifest file.
+pub fn get_archive_plugin_file<TEnvironment: Environment>(
+  environment: &TEnvironment,
+  archive_url: &ChecksumUrl,
+  binary_name: &BinaryName,
+  version: &Version,
+  commands: Vec<PlatformInfoCommand>,
+  download_type: DownloadType,
+) -> Result<PluginFile, ErrBox> {
+  verify_plugin_binary_name(&binary_name.owner)?;
+  verify_plugin_binary_name(&binary_name.name)?;
+
+  // download the archive once and keep it for setting up the plugin
+  let mut stream = environment.fetch_url_stream(&archive_url.url)?;
+  let checksum = match &archive_url.checksum {
+    Some(checksum) => {
+      verify_sha256_checksum_from_reader(&mut stream, checksum)?;
+      checksum.clone()
+    }
+    None => get_sha256_checksum_from_reader(&mut stream)?,
+  };
+  stream.rewind()?;
+
+  let mut file = SerializedPluginFile {
+    schema_version: 1,
+    name: binary_name.name.clone(),
+    owner: binary_name.owner.clone(),
+    version: version.clone(),
+    description: String::new(),
+    linux: None,
+    mac: None,
+    windows: None,
+    dependencies: Vec::new(),
+  };
+  *file.get_platform_info_mut(PluginPlatform::current()) = Some(PlatformInfo {
+    path: archive_url.url.to_string(),
+    checksum: checksum.clone(),
+    download_type: download_type.as_str().to_string(),
+    output_dir: None,
+    commands,
+    on_pre_install: None,
+    on_post_install: None,
+    on_pre_uninstall: None,
+    on_post_uninstall: None,
+    environment: None,
+  });
+
+  Ok(PluginFile {
+    url: archive_url.url.clone(),
+    checksum,
+    file,
+    is_archive: true,
+    archive: Mutex::new(Some(stream)),
+  })
+}
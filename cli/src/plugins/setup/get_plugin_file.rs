@@ -1,10 +1,14 @@
 use dprint_cli_core::checksums::get_sha256_checksum;
 use dprint_cli_core::checksums::verify_sha256_checksum;
 use dprint_cli_core::types::ErrBox;
+use std::sync::Mutex;
+use url::Url;
 
+use super::get_archive_plugin_file;
 use super::read_plugin_file;
-use super::PluginFile;
+use super::{DownloadType, PluginFile};
 use crate::environment::Environment;
+use crate::plugins::{BinaryManifestItem, PlatformInfoCommand};
 use crate::utils::ChecksumUrl;
 
 pub fn get_plugin_file<TEnvironment: Environment>(
@@ -24,5 +28,38 @@ pub fn get_plugin_file<TEnvironment: Environment>(
     url: checksum_url.url.clone(),
     checksum,
     file: serialized_plugin_file,
+    is_archive: false,
+    archive: Mutex::new(None),
   })
 }
+
+/// Gets the plugin file for reinstalling an installed binary from its source.
+pub fn get_installed_plugin_file<TEnvironment: Environment>(
+  environment: &TEnvironment,
+  item: &BinaryManifestItem,
+) -> Result<PluginFile, ErrBox> {
+  let source = &item.source;
+  let checksum_url = ChecksumUrl {
+    unresolved_path: source.path.clone(),
+    url: Url::parse(&source.path)?,
+    checksum: Some(source.checksum.clone()),
+  };
+  match &source.download_type {
+    Some(download_type) => get_archive_plugin_file(
+      environment,
+      &checksum_url,
+      &item.name,
+      &item.version,
+      item
+        .commands
+        .iter()
+        .map(|command| PlatformInfoCommand {
+          name: command.name.clone(),
+          path: command.path.clone(),
+        })
+        .collect(),
+      DownloadType::parse(download_type)?,
+    ),
+    None => get_plugin_file(environment, &checksum_url),
+  }
+}
@@ -0,0 +1,70 @@
+use dprint_cli_core::types::ErrBox;
+use std::path::Path;
+
+use super::{create_shim, verify_commands, verify_plugin_binary_name};
+use crate::environment::Environment;
+use crate::plugins::{BinaryManifestItem, BinaryManifestItemCommand, BinaryManifestItemSource, PlatformInfoCommand};
+use crate::types::{BinaryName, Version};
+use crate::utils;
+
+/// Creates the manifest item for a binary whose files are used directly from
+/// the provided directory rather than being copied to the binaries directory.
+pub fn link_binary<TEnvironment: Environment>(
+  environment: &TEnvironment,
+  dir_path: &Path,
+  binary_name: &BinaryName,
+  version: &Version,
+  commands: &Vec<PlatformInfoCommand>,
+) -> Result<BinaryManifestItem, ErrBox> {
+  verify_plugin_binary_name(&binary_name.owner)?;
+  verify_plugin_binary_name(&binary_name.name)?;
+  verify_commands(commands)?;
+
+  let command_paths = commands
+    .iter()
+    .map(|command| {
+      dir_path.join(if cfg!(windows) {
+        command.path.replace("/", "\\")
+      } else {
+        command.path.clone()
+      })
+    })
+    .collect::<Vec<_>>();
+  for (command, command_path) in commands.iter().zip(command_paths.iter()) {
+    if !environment.path_exists(command_path) {
+      return err!(
+        "Could not find the executable for command '{}' at {}.",
+        command.name,
+        command_path.display()
+      );
+    }
+  }
+
+  environment.create_dir_all(utils::get_shim_dir(environment))?;
+  for command in commands.iter() {
+    create_shim(environment, &command.name)?;
+  }
+
+  Ok(BinaryManifestItem {
+    name: binary_name.clone(),
+    version: version.clone(),
+    created_time: environment.get_time_secs(),
+    commands: commands
+      .iter()
+      .map(|c| BinaryManifestItemCommand {
+        name: c.name.clone(),
+        path: c.path.clone(),
+      })
+      .collect(),
+    source: BinaryManifestItemSource {
+      path: dir_path.to_string_lossy().to_string(),
+      checksum: String::new(),
+      download_type: None,
+    },
+    environment: None,
+    linked_dir: Some(dir_path.to_path_buf()),
+    on_pre_uninstall: None,
+    on_post_uninstall: None,
+    dependencies: Vec::new(),
+  })
+}
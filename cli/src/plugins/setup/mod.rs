@@ -1,9 +1,15 @@
 mod create_shim;
+mod download_archives;
+mod get_archive_plugin_file;
 mod get_plugin_file;
+mod link_binary;
 mod read_plugin_file;
 mod setup_plugin;
 
 pub use create_shim::*;
+pub use download_archives::*;
+pub use get_archive_plugin_file::*;
 pub use get_plugin_file::*;
-use read_plugin_file::*;
+pub use link_binary::*;
+pub use read_plugin_file::*;
 pub use setup_plugin::*;
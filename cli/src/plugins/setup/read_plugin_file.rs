@@ -1,6 +1,37 @@
 use dprint_cli_core::types::ErrBox;
+use std::path::PathBuf;
 
-use crate::plugins::SerializedPluginFile;
+use crate::plugins::{PluginDependency, PluginPlatform, SerializedPluginFile};
+use crate::types::{CommandName, VersionSelector};
+
+/// Variables a binary may not set unless the user allows them with the
+/// `allowedEnvVars` setting because they change how shells, the dynamic
+/// loader, or bvm itself behave.
+const RESTRICTED_ENV_VAR_NAMES: [&str; 22] = [
+  "PATH",
+  "PATHEXT",
+  "HOME",
+  "SHELL",
+  "ENV",
+  "BASH_ENV",
+  "BASHOPTS",
+  "SHELLOPTS",
+  "PROMPT_COMMAND",
+  "PS1",
+  "PS4",
+  "IFS",
+  "CDPATH",
+  "ZDOTDIR",
+  "COMSPEC",
+  "PSMODULEPATH",
+  "LD_PRELOAD",
+  "LD_LIBRARY_PATH",
+  "LD_AUDIT",
+  "DYLD_INSERT_LIBRARIES",
+  "DYLD_LIBRARY_PATH",
+  "DYLD_FRAMEWORK_PATH",
+];
+const RESTRICTED_ENV_VAR_PREFIXES: [&str; 2] = ["BVM_", "BASH_FUNC_"];
 
 pub fn read_plugin_file(file_bytes: &[u8]) -> Result<SerializedPluginFile, ErrBox> {
   // todo: don't use serde because this should fail with a nice error message if the schema version is not equal
@@ -12,23 +43,32 @@ pub fn read_plugin_file(file_bytes: &[u8]) -> Result<SerializedPluginFile, ErrBo
                     file.schema_version
                 );
       }
-      // Validate the binary owner and name
-      if file.name.starts_with(".") || file.name.starts_with("_") {
-        return err!("The binary owner and name should not start with '.' or '_'");
-      } else if file.name == "bvm" {
-        return err!("'bvm' is not allowed to be used as a binary name");
-      } else if file.name.contains("||")
-        || file.name.contains("~")
-        || file.name.contains("(")
-        || file.name.contains(")")
-        || file.name.contains("'")
-        || file.name.contains("!")
-        || file.name.contains("*")
-        || file.name.contains("/")
-      {
-        return err!("The binary owner and name may not contain any of these characters(||,~,(,),',!,*,/)");
-      } else if file.name.len() > 224 {
-        return err!("The binary owner and name should not execced 224 characters");
+      verify_plugin_binary_name(&file.name)?;
+      for dependency in file.dependencies.iter() {
+        verify_dependency(dependency)?;
+      }
+      for platform in PluginPlatform::all() {
+        let platform_info = match file.get_platform_info(platform) {
+          Some(platform_info) => platform_info,
+          None => continue,
+        };
+        for command in platform_info.commands.iter() {
+          verify_command_name(&command.name)?;
+        }
+        let variables = platform_info
+          .environment
+          .as_ref()
+          .and_then(|env| env.variables.as_ref());
+        if let Some(variables) = variables {
+          for (key, value) in variables.iter() {
+            verify_env_var_name(key)?;
+            verify_env_var_value(platform, key, value)?;
+          }
+        }
+        let paths = platform_info.environment.as_ref().and_then(|env| env.paths.as_ref());
+        for path in paths.into_iter().flatten() {
+          verify_env_path(platform, path)?;
+        }
       }
 
       Ok(file)
@@ -36,3 +76,186 @@ pub fn read_plugin_file(file_bytes: &[u8]) -> Result<SerializedPluginFile, ErrBo
     Err(err) => err!("Error deserializing binary manifest file. {}", err.to_string()),
   }
 }
+
+fn verify_dependency(dependency: &PluginDependency) -> Result<(), ErrBox> {
+  if dependency.name.trim().is_empty() {
+    return err!("A dependency must have a name.");
+  }
+  if let Some(version) = &dependency.version {
+    if let Err(err) = VersionSelector::parse_for_config(version) {
+      return err!(
+        "Invalid version for dependency '{}'. {}",
+        dependency.name,
+        err.to_string()
+      );
+    }
+  }
+  if dependency.url.is_some() && dependency.registry.is_some() {
+    return err!(
+      "Dependency '{}' cannot specify both a url and a registry.",
+      dependency.name
+    );
+  }
+  Ok(())
+}
+
+/// Validates the name of a binary from a binary manifest file.
+pub fn verify_plugin_binary_name(name: &str) -> Result<(), ErrBox> {
+  if name.starts_with(".") || name.starts_with("_") {
+    return err!("The binary owner and name should not start with '.' or '_'");
+  } else if name == "bvm" {
+    return err!("'bvm' is not allowed to be used as a binary name");
+  } else if name.contains("||")
+    || name.contains("~")
+    || name.contains("(")
+    || name.contains(")")
+    || name.contains("'")
+    || name.contains("!")
+    || name.contains("*")
+    || name.contains("/")
+  {
+    return err!("The binary owner and name may not contain any of these characters(||,~,(,),',!,*,/)");
+  } else if name.len() > 224 {
+    return err!("The binary owner and name should not execced 224 characters");
+  }
+
+  Ok(())
+}
+
+/// Validates a command name, which is used as the file name of its shim and
+/// is run by the shells.
+pub fn verify_command_name(command_name: &CommandName) -> Result<(), ErrBox> {
+  let name = command_name.as_str();
+  let is_valid_chars = name
+    .chars()
+    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'));
+  if name.is_empty() || !is_valid_chars {
+    return err!(
+      "Invalid command name '{}'. Command names may only contain letters, numbers, and these characters (-,_,.,+)",
+      name
+    );
+  } else if name.starts_with('.') || name.starts_with('-') {
+    return err!(
+      "Invalid command name '{}'. Command names should not start with '.' or '-'",
+      name
+    );
+  } else if name == "bvm" || name == "bvm-bin" {
+    return err!("'{}' is not allowed to be used as a command name", name);
+  } else if name.len() > 100 {
+    return err!(
+      "Invalid command name '{}'. Command names should not exceed 100 characters",
+      name
+    );
+  }
+
+  Ok(())
+}
+
+/// Validates the name of an environment variable is a portable identifier.
+pub fn verify_env_var_name(name: &str) -> Result<(), ErrBox> {
+  let mut chars = name.chars();
+  let is_valid = match chars.next() {
+    Some(c) => (c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+    None => false,
+  };
+  if !is_valid {
+    return err!(
+      "Invalid environment variable name '{}'. Names must start with a letter or underscore and only contain letters, numbers, and underscores.",
+      name
+    );
+  }
+  Ok(())
+}
+
+fn verify_env_var_value(platform: PluginPlatform, name: &str, value: &str) -> Result<(), ErrBox> {
+  // new lines and tabs are quoted when output for a shell
+  if value
+    .chars()
+    .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+  {
+    return err!(
+      "Invalid value for the environment variable '{}'. Values may not contain control characters.",
+      name
+    );
+  }
+  // cmd may treat these as quotes or variable references, such as when the user enabled delayed expansion
+  if platform == PluginPlatform::Windows && value.contains(['"', '%', '!']) {
+    return err!(
+      "Invalid value for the environment variable '{}'. Values on Windows may not contain double quotes, '%', or '!'.",
+      name
+    );
+  }
+  Ok(())
+}
+
+/// Validates a directory a binary adds to the path can't add other directories
+/// or go down out of the binary's directory.
+fn verify_env_path(platform: PluginPlatform, path: &str) -> Result<(), ErrBox> {
+  let delimiter = match platform {
+    PluginPlatform::Windows => ';',
+    PluginPlatform::Linux | PluginPlatform::Mac => ':',
+  };
+  if path.contains(delimiter) || path.chars().any(|c| c.is_control()) {
+    return err!(
+      "Invalid environment path '{}'. Paths may not contain '{}' or control characters.",
+      path,
+      delimiter
+    );
+  }
+  if path.split(['/', '\\']).any(|part| part == "..") {
+    return err!(
+      "Invalid environment path '{}'. A path cannot go down directories.",
+      path
+    );
+  }
+  Ok(())
+}
+
+/// Errors if any of the paths are outside the binary's directory, which a binary
+/// may only add to the path when the user allows it to set `PATH`.
+pub fn verify_env_paths_allowed<'a>(
+  paths: impl Iterator<Item = &'a String>,
+  allowed_names: &[String],
+) -> Result<(), ErrBox> {
+  if allowed_names.iter().any(|allowed| allowed.eq_ignore_ascii_case("PATH")) {
+    return Ok(());
+  }
+  for path in paths {
+    let is_binary_dir = path.starts_with("$BVM_CURRENT_BINARY_DIR") || path.starts_with("%BVM_CURRENT_BINARY_DIR%");
+    if !is_binary_dir && (path.starts_with('/') || path.starts_with('\\') || PathBuf::from(path).is_absolute()) {
+      return err!(
+        "The binary is not allowed to add '{}' to the path because it's outside the binary's directory. If you trust the binary, add 'PATH' to the allowedEnvVars setting.",
+        path
+      );
+    }
+  }
+  Ok(())
+}
+
+/// Errors if any of the variables may only be set by a binary when the user allows it.
+pub fn verify_env_vars_allowed<'a>(
+  names: impl Iterator<Item = &'a String>,
+  allowed_names: &[String],
+) -> Result<(), ErrBox> {
+  for name in names {
+    let upper_name = name.to_uppercase(); // environment variables are case insensitive on Windows
+    let is_restricted = RESTRICTED_ENV_VAR_NAMES.contains(&upper_name.as_str())
+      || RESTRICTED_ENV_VAR_PREFIXES
+        .iter()
+        .any(|prefix| upper_name.starts_with(prefix));
+    if is_restricted && !allowed_names.iter().any(|allowed| allowed.eq_ignore_ascii_case(name)) {
+      let path_hint = if upper_name == "PATH" {
+        " Binaries should specify directories in \"paths\" instead."
+      } else {
+        ""
+      };
+      return err!(
+        "The binary is not allowed to set the environment variable '{}'.{} If you trust the binary, add '{}' to the allowedEnvVars setting.",
+        name,
+        path_hint,
+        name
+      );
+    }
+  }
+  Ok(())
+}
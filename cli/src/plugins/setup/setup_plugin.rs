@@ -1,14 +1,18 @@
-use dprint_cli_core::checksums::verify_sha256_checksum;
 use dprint_cli_core::types::ErrBox;
 use std::path::PathBuf;
+use std::sync::Mutex;
 use url::Url;
 
 use super::create_shim;
-use crate::environment::Environment;
+use super::verify_command_name;
+use super::verify_env_paths_allowed;
+use super::verify_env_vars_allowed;
+use crate::environment::{Environment, FileStream};
 use crate::plugins::{
   get_plugin_dir, BinaryEnvironment, BinaryIdentifier, BinaryManifestItem, BinaryManifestItemCommand,
-  BinaryManifestItemSource, PlatformInfo, PlatformInfoCommand, SerializedPluginFile,
+  BinaryManifestItemSource, PlatformInfo, PlatformInfoCommand, PluginDependency, SerializedPluginFile,
 };
+use crate::scripts::{run_script, ScriptKind, ScriptTarget, ScriptsMode};
 use crate::types::{BinaryName, Version};
 use crate::utils::{self, parse_path_or_url_to_url};
 
@@ -18,14 +22,51 @@ pub struct PluginFile {
   pub checksum: String,
 
   pub(super) file: SerializedPluginFile,
+  /// If the url is an archive rather than a binary manifest file.
+  pub(super) is_archive: bool,
+  /// The verified archive when it was downloaded before setting up the plugin.
+  pub(super) archive: Mutex<Option<FileStream>>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DownloadType {
   Zip,
   Binary,
   TarGz,
 }
 
+impl DownloadType {
+  pub fn parse(text: &str) -> Result<DownloadType, ErrBox> {
+    let download_type = text.to_lowercase();
+    Ok(match download_type.as_str() {
+      "zip" => DownloadType::Zip,
+      "binary" => DownloadType::Binary,
+      "tar.gz" => DownloadType::TarGz,
+      _ => return err!("Unknown download type: {}", download_type),
+    })
+  }
+
+  /// Infers the download type from the extension of the provided path.
+  pub fn from_path(path: &str) -> DownloadType {
+    let path = path.to_lowercase();
+    if path.ends_with(".zip") {
+      DownloadType::Zip
+    } else if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+      DownloadType::TarGz
+    } else {
+      DownloadType::Binary
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      DownloadType::Zip => "zip",
+      DownloadType::Binary => "binary",
+      DownloadType::TarGz => "tar.gz",
+    }
+  }
+}
+
 impl PluginFile {
   pub fn display(&self) -> String {
     format!("{}/{} {}", self.file.owner, self.file.name, self.file.version)
@@ -39,6 +80,14 @@ impl PluginFile {
     &self.file.version
   }
 
+  pub fn description(&self) -> &str {
+    &self.file.description
+  }
+
+  pub fn get_dependencies(&self) -> &Vec<PluginDependency> {
+    &self.file.dependencies
+  }
+
   pub fn get_url(&self) -> Result<&String, ErrBox> {
     Ok(&self.get_platform_info()?.path)
   }
@@ -56,13 +105,7 @@ impl PluginFile {
   }
 
   pub fn get_download_type(&self) -> Result<DownloadType, ErrBox> {
-    let download_type = self.get_platform_info()?.download_type.to_lowercase();
-    Ok(match download_type.as_str() {
-      "zip" => DownloadType::Zip,
-      "binary" => DownloadType::Binary,
-      "tar.gz" => DownloadType::TarGz,
-      _ => return err!("Unknown download type: {}", download_type),
-    })
+    DownloadType::parse(&self.get_platform_info()?.download_type)
   }
 
   pub fn get_output_dir(&self) -> Result<&Option<String>, ErrBox> {
@@ -77,6 +120,38 @@ impl PluginFile {
     Ok(&self.get_platform_info()?.on_post_install)
   }
 
+  pub fn get_pre_uninstall_command(&self) -> Result<&Option<String>, ErrBox> {
+    Ok(&self.get_platform_info()?.on_pre_uninstall)
+  }
+
+  pub fn get_post_uninstall_command(&self) -> Result<&Option<String>, ErrBox> {
+    Ok(&self.get_platform_info()?.on_post_uninstall)
+  }
+
+  /// Downloads the archive and verifies its checksum so that setting up the
+  /// plugin uses it rather than downloading it again.
+  pub fn download_archive(&self, environment: &impl Environment) -> Result<(), ErrBox> {
+    let stream = self.fetch_archive(environment)?;
+    *self.archive.lock().unwrap() = Some(stream);
+    Ok(())
+  }
+
+  fn take_archive(&self, environment: &impl Environment) -> Result<FileStream, ErrBox> {
+    let archive = self.archive.lock().unwrap().take();
+    match archive {
+      Some(stream) => Ok(stream),
+      None => self.fetch_archive(environment),
+    }
+  }
+
+  fn fetch_archive(&self, environment: &impl Environment) -> Result<FileStream, ErrBox> {
+    let url = parse_path_or_url_to_url(self.get_url()?, &self.url)?;
+    let mut stream = environment.fetch_url_stream(&url)?;
+    utils::verify_sha256_checksum_from_reader(&mut stream, self.get_url_checksum()?)?;
+    stream.rewind()?;
+    Ok(stream)
+  }
+
   fn get_platform_info(&self) -> Result<&PlatformInfo, ErrBox> {
     // todo: how to throw a nice compile error here for an unsupported OS?
     #[cfg(target_os = "linux")]
@@ -106,25 +181,27 @@ fn get_plugin_platform_info<'a>(platform_info: &'a Option<PlatformInfo>) -> Resu
 pub fn setup_plugin<'a, TEnvironment: Environment>(
   environment: &TEnvironment,
   plugin_file: &PluginFile,
+  scripts_mode: ScriptsMode,
 ) -> Result<BinaryManifestItem, ErrBox> {
-  // download the url's bytes
-  let url = plugin_file.get_url()?;
-  let url = parse_path_or_url_to_url(&url, &plugin_file.url)?;
+  if let Some(binary_environment) = plugin_file.get_environment()? {
+    let allowed_env_vars = environment.get_settings().allowed_env_vars();
+    if let Some(variables) = &binary_environment.variables {
+      verify_env_vars_allowed(variables.keys(), &allowed_env_vars)?;
+    }
+    if let Some(paths) = &binary_environment.paths {
+      verify_env_paths_allowed(paths.iter(), &allowed_env_vars)?;
+    }
+  }
+
+  // download the url to a stream and verify it before extracting
   let download_type = plugin_file.get_download_type()?;
-  let url_file_bytes = environment.fetch_url(&url)?;
-  verify_sha256_checksum(&url_file_bytes, plugin_file.get_url_checksum()?)?;
+  let mut stream = plugin_file.take_archive(environment)?;
 
   // create folder
   let plugin_cache_dir_path = get_plugin_dir(environment, &plugin_file.get_binary_name(), &plugin_file.version());
   let _ignore = environment.remove_dir_all(&plugin_cache_dir_path);
   environment.create_dir_all(&plugin_cache_dir_path)?;
 
-  // run the pre install command
-  if let Some(pre_install_command) = plugin_file.get_pre_install_command()? {
-    environment.run_shell_command(&plugin_cache_dir_path, pre_install_command)?;
-  }
-
-  // handle the setup based on the download type
   let commands = plugin_file.get_commands()?;
   verify_commands(commands)?;
   let output_dir = if let Some(output_dir) = plugin_file.get_output_dir()? {
@@ -135,44 +212,70 @@ pub fn setup_plugin<'a, TEnvironment: Environment>(
   } else {
     plugin_cache_dir_path.clone()
   };
+  let binary_name = plugin_file.get_binary_name();
+  let script_target = ScriptTarget::Binary {
+    name: &binary_name,
+    version: plugin_file.version(),
+    binary_dir: &plugin_cache_dir_path,
+    output_dir: Some(&output_dir),
+  };
+
+  // run the pre install command
+  if let Some(pre_install_command) = plugin_file.get_pre_install_command()? {
+    run_script(
+      environment,
+      ScriptKind::PreInstall,
+      plugin_file.url.as_str(),
+      pre_install_command,
+      &plugin_cache_dir_path,
+      &script_target,
+      scripts_mode,
+    )?;
+  }
+
+  // handle the setup based on the download type
   match download_type {
     DownloadType::Zip => utils::extract_zip(
       &format!("Extracting archive for {}...", plugin_file.display(),),
       environment,
-      &url_file_bytes,
-      &output_dir,
-    )?,
-    DownloadType::TarGz => utils::extract_tar_gz(
-      &format!("Extracting archive for {}...", plugin_file.display(),),
-      environment,
-      &url_file_bytes,
+      stream,
       &output_dir,
     )?,
+    DownloadType::TarGz => {
+      let total_size = stream.len();
+      utils::extract_tar_gz(
+        &format!("Extracting archive for {}...", plugin_file.display(),),
+        environment,
+        stream,
+        total_size,
+        &output_dir,
+      )?
+    }
     DownloadType::Binary => {
       if commands.len() != 1 {
         return err!("The binary download type must have exactly one command specified.");
       }
-      environment.write_file(&output_dir.join(&commands[0].path), &url_file_bytes)?
+      environment.write_file_from_reader(&output_dir.join(&commands[0].path), &mut stream)?
     }
   }
 
   // run the post install command
   if let Some(post_install_command) = plugin_file.get_post_install_command()? {
-    environment.run_shell_command(&plugin_cache_dir_path, post_install_command)?;
+    run_script(
+      environment,
+      ScriptKind::PostInstall,
+      plugin_file.url.as_str(),
+      post_install_command,
+      &plugin_cache_dir_path,
+      &script_target,
+      scripts_mode,
+    )?;
   }
 
   // create the shims after in case the post install fails
   environment.create_dir_all(&utils::get_shim_dir(environment))?;
   for command in commands {
-    create_shim(
-      environment,
-      &command.name,
-      &output_dir.join(if cfg!(windows) {
-        command.path.replace("/", "\\")
-      } else {
-        command.path.clone()
-      }),
-    )?;
+    create_shim(environment, &command.name)?;
   }
 
   // add the plugin information to the manifest
@@ -190,26 +293,36 @@ pub fn setup_plugin<'a, TEnvironment: Environment>(
     source: BinaryManifestItemSource {
       path: plugin_file.url.to_string(),
       checksum: plugin_file.checksum.clone(),
+      download_type: if plugin_file.is_archive {
+        Some(download_type.as_str().to_string())
+      } else {
+        None
+      },
     },
     environment: plugin_file.get_environment()?.clone(),
+    linked_dir: None,
+    on_pre_uninstall: plugin_file.get_pre_uninstall_command()?.clone(),
+    on_post_uninstall: plugin_file.get_post_uninstall_command()?.clone(),
+    dependencies: Vec::new(),
   };
   Ok(item)
 }
 
-fn verify_commands(commands: &Vec<PlatformInfoCommand>) -> Result<(), ErrBox> {
+pub(super) fn verify_commands(commands: &Vec<PlatformInfoCommand>) -> Result<(), ErrBox> {
   if commands.is_empty() {
     return err!("One command must be specified.");
   }
 
   // prevent funny business
   for command in commands.iter() {
+    verify_command_name(&command.name)?;
     verify_valid_relative_path(&command.path)?;
   }
 
   Ok(())
 }
 
-fn verify_valid_relative_path(path: &str) -> Result<(), ErrBox> {
+pub fn verify_valid_relative_path(path: &str) -> Result<(), ErrBox> {
   if path.contains("../") || path.contains("..\\") {
     return err!("Invalid path '{}'. A path cannot go down directories.", path);
   }
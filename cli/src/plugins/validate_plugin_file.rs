@@ -0,0 +1,167 @@
+use dprint_cli_core::types::ErrBox;
+use std::path::PathBuf;
+use url::Url;
+
+use super::setup::verify_valid_relative_path;
+use super::{get_archive_files, DownloadType, PlatformInfo, PluginPlatform, SerializedPluginFile};
+use crate::environment::Environment;
+use crate::utils::{get_sha256_checksum_from_reader, parse_path_or_url_to_url, ArchiveFile};
+
+/// Downloads the archive of each platform and checks that it matches the plugin file.
+/// Returns a description of every problem found.
+pub fn validate_plugin_file(
+  environment: &impl Environment,
+  plugin_file: &SerializedPluginFile,
+  base: &Url,
+) -> Vec<String> {
+  let mut problems = Vec::new();
+  for platform in PluginPlatform::all() {
+    if let Some(platform_info) = plugin_file.get_platform_info(platform) {
+      let mut platform_problems = Vec::new();
+      if let Err(err) = validate_platform_info(environment, platform, platform_info, base, &mut platform_problems) {
+        platform_problems.push(err.to_string());
+      }
+      problems.extend(
+        platform_problems
+          .into_iter()
+          .map(|problem| format!("{}: {}", platform.as_str(), problem)),
+      );
+    }
+  }
+
+  if plugin_file.linux.is_none() && plugin_file.mac.is_none() && plugin_file.windows.is_none() {
+    problems.push("At least one platform must be specified.".to_string());
+  }
+
+  problems
+}
+
+fn validate_platform_info(
+  environment: &impl Environment,
+  platform: PluginPlatform,
+  platform_info: &PlatformInfo,
+  base: &Url,
+  problems: &mut Vec<String>,
+) -> Result<(), ErrBox> {
+  let download_type = DownloadType::parse(&platform_info.download_type)?;
+  let output_dir = match &platform_info.output_dir {
+    Some(output_dir) => {
+      verify_valid_relative_path(output_dir)?;
+      normalize_path(output_dir)
+    }
+    None => String::new(),
+  };
+
+  if platform_info.commands.is_empty() {
+    problems.push("One command must be specified.".to_string());
+  }
+  for command in platform_info.commands.iter() {
+    if let Err(err) = verify_valid_relative_path(&command.path) {
+      problems.push(err.to_string());
+    }
+  }
+
+  let url = parse_path_or_url_to_url(&platform_info.path, base)?;
+  let mut stream = environment.fetch_url_stream(&url)?;
+  let checksum = get_sha256_checksum_from_reader(&mut stream)?;
+  if checksum != platform_info.checksum {
+    problems.push(format!(
+      "The checksum did not match the archive. Expected {}, but found {}.",
+      platform_info.checksum, checksum
+    ));
+  }
+
+  // the files in the plugin directory after extracting
+  let files = match download_type {
+    DownloadType::Binary => {
+      if platform_info.commands.len() != 1 {
+        problems.push("The binary download type must have exactly one command specified.".to_string());
+      }
+      platform_info
+        .commands
+        .iter()
+        .map(|command| ArchiveFile {
+          path: join_path(&output_dir, &normalize_path(&command.path)),
+          unix_mode: None,
+        })
+        .collect()
+    }
+    _ => get_archive_files(download_type, &mut stream)?
+      .into_iter()
+      .map(|file| ArchiveFile {
+        path: join_path(&output_dir, &file.path),
+        unix_mode: file.unix_mode,
+      })
+      .collect::<Vec<_>>(),
+  };
+
+  for command in platform_info.commands.iter() {
+    let command_path = join_path(&output_dir, &normalize_path(&command.path));
+    match files.iter().find(|file| file.path == command_path) {
+      Some(file) => {
+        if platform != PluginPlatform::Windows && !file.is_executable() {
+          problems.push(format!(
+            "The path '{}' for command '{}' is not executable.",
+            command.path, command.name
+          ));
+        }
+      }
+      None => problems.push(format!(
+        "The path '{}' for command '{}' does not exist in the archive.",
+        command.path, command.name
+      )),
+    }
+  }
+
+  let env_paths = platform_info
+    .environment
+    .as_ref()
+    .and_then(|e| e.paths.as_ref())
+    .cloned()
+    .unwrap_or_default();
+  for env_path in env_paths {
+    // relative paths are relative to the binary's directory
+    let dir_path = if let Some(path) = env_path
+      .strip_prefix("$BVM_CURRENT_BINARY_DIR")
+      .or_else(|| env_path.strip_prefix("%BVM_CURRENT_BINARY_DIR%"))
+    {
+      normalize_path(path)
+    } else if env_path.starts_with('/') || PathBuf::from(&env_path).is_absolute() {
+      continue;
+    } else {
+      normalize_path(&env_path)
+    };
+    let dir_prefix = format!("{}/", dir_path);
+    if !dir_path.is_empty() && !files.iter().any(|file| file.path.starts_with(&dir_prefix)) {
+      problems.push(format!(
+        "The environment path '{}' does not exist in the archive.",
+        env_path
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+fn normalize_path(path: &str) -> String {
+  let path = path.replace('\\', "/");
+  let mut path = path.as_str();
+  loop {
+    if let Some(stripped) = path.strip_prefix("./") {
+      path = stripped;
+    } else if let Some(stripped) = path.strip_prefix('/') {
+      path = stripped;
+    } else {
+      break;
+    }
+  }
+  path.trim_end_matches('/').to_string()
+}
+
+fn join_path(dir: &str, path: &str) -> String {
+  if dir.is_empty() {
+    path.to_string()
+  } else {
+    format!("{}/{}", dir, path)
+  }
+}
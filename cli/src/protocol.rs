@@ -0,0 +1,92 @@
+use dprint_cli_core::types::ErrBox;
+use std::path::Path;
+
+use crate::environment::Environment;
+use crate::utils::{quote_fish, quote_powershell, quote_sh};
+
+/// Version of the messages the hidden commands output for the shell scripts.
+///
+/// Increment this whenever the messages change so that out of date shell
+/// scripts error via `bvm hidden protocol-version` instead of misbehaving.
+pub const PROTOCOL_VERSION: u32 = 2;
+const SHELL_ENV_VAR_NAME: &str = "BVM_SHELL";
+
+/// A change for a shell script to apply.
+pub enum Message<'a> {
+  SetEnvVar(&'a str, &'a str),
+  UnsetEnvVar(&'a str),
+  /// The executable to run with the remaining arguments.
+  Exec(&'a Path),
+}
+
+/// The shell the messages are output for, which is provided by the shell scripts
+/// in the `BVM_SHELL` environment variable.
+///
+/// Messages are shell code that calls the `__bvm_set <key> <value>`,
+/// `__bvm_unset <key>`, and `__bvm_exec <path>` functions defined by the
+/// scripts, where each argument is quoted for the shell. The exception is cmd,
+/// which runs `SET "<key>=<value>"` lines and receives the path in an
+/// `EXEC <path>` line. The lines are run with delayed expansion disabled, so
+/// the values are output as is because cmd doesn't expand `%`, `!`, or `^`
+/// in the output of a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageShell {
+  Sh,
+  Fish,
+  Powershell,
+  Cmd,
+}
+
+impl MessageShell {
+  pub fn from_env(environment: &impl Environment) -> Result<MessageShell, ErrBox> {
+    match environment.get_env_var(SHELL_ENV_VAR_NAME).as_deref() {
+      Some("sh") => Ok(MessageShell::Sh),
+      Some("fish") => Ok(MessageShell::Fish),
+      Some("powershell") => Ok(MessageShell::Powershell),
+      Some("cmd") => Ok(MessageShell::Cmd),
+      Some("") | None => Ok(if cfg!(target_os = "windows") {
+        MessageShell::Cmd
+      } else {
+        MessageShell::Sh
+      }),
+      Some(value) => err!(
+        "Unsupported {} value: {}\n\nThe bvm shell scripts may be out of date. Try running the bvm install script again.",
+        SHELL_ENV_VAR_NAME,
+        value
+      ),
+    }
+  }
+
+  pub fn format(&self, message: &Message) -> Result<String, ErrBox> {
+    if let MessageShell::Cmd = self {
+      return format_cmd(message);
+    }
+
+    let quote = match self {
+      MessageShell::Sh => quote_sh,
+      MessageShell::Fish => quote_fish,
+      MessageShell::Powershell => quote_powershell,
+      MessageShell::Cmd => unreachable!(),
+    };
+    Ok(match message {
+      Message::SetEnvVar(key, value) => format!("__bvm_set {} {}", quote(key), quote(value)),
+      Message::UnsetEnvVar(key) => format!("__bvm_unset {}", quote(key)),
+      Message::Exec(path) => format!("__bvm_exec {}", quote(&path.to_string_lossy())),
+    })
+  }
+}
+
+fn format_cmd(message: &Message) -> Result<String, ErrBox> {
+  let (key, value) = match message {
+    Message::SetEnvVar(key, value) => (*key, *value),
+    Message::UnsetEnvVar(key) => (*key, ""),
+    Message::Exec(path) => return Ok(format!("EXEC {}", path.display())),
+  };
+  if key.contains(['\n', '\r']) || value.contains(['\n', '\r']) {
+    return err!(
+      "The environment variable {} could not be set because cmd does not support new lines in environment variables.",
+      key.trim()
+    );
+  }
+  Ok(format!("SET \"{}={}\"", key, value))
+}
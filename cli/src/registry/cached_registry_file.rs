@@ -0,0 +1,50 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_cli_core::types::ErrBox;
+use std::path::PathBuf;
+
+use super::{download_registry_file, read_registry_file, RegistryFile};
+use crate::environment::Environment;
+
+/// Gets the registry file from the cache, downloading it if it hasn't been cached.
+pub fn get_cached_registry_file(environment: &impl Environment, url: &str) -> Result<RegistryFile, ErrBox> {
+  let file_path = get_cached_registry_file_path(environment, url);
+  if let Ok(file_bytes) = environment.read_file(&file_path) {
+    match read_registry_file(&file_bytes) {
+      Ok(file) => return Ok(file),
+      Err(err) => log_verbose!(
+        environment,
+        "Ignoring cached registry file for {}: {}",
+        url,
+        err.to_string()
+      ),
+    }
+  }
+
+  download_registry_file(environment, url)
+}
+
+pub fn cache_registry_file_bytes(environment: &impl Environment, url: &str, file_bytes: &[u8]) -> Result<(), ErrBox> {
+  environment.create_dir_all(get_cached_registry_files_dir(environment))?;
+  environment.write_file(get_cached_registry_file_path(environment, url), file_bytes)
+}
+
+pub fn remove_cached_registry_file(environment: &impl Environment, url: &str) -> Result<(), ErrBox> {
+  let file_path = get_cached_registry_file_path(environment, url);
+  if environment.path_exists(&file_path) {
+    environment.remove_file(&file_path)?;
+  }
+  Ok(())
+}
+
+pub fn clear_cached_registry_files(environment: &impl Environment) -> Result<(), ErrBox> {
+  environment.remove_dir_all(get_cached_registry_files_dir(environment))
+}
+
+fn get_cached_registry_file_path(environment: &impl Environment, url: &str) -> PathBuf {
+  get_cached_registry_files_dir(environment).join(format!("{}.json", get_sha256_checksum(url.as_bytes())))
+}
+
+fn get_cached_registry_files_dir(environment: &impl Environment) -> PathBuf {
+  let local_data_dir = environment.get_local_user_data_dir(); // do not share across domains
+  local_data_dir.join("registries")
+}
@@ -0,0 +1,63 @@
+use dprint_cli_core::types::ErrBox;
+
+use super::{read_registry_file, RegistryBinary, RegistryFile, RegistryVersionInfo};
+use crate::types::BinaryName;
+
+pub fn create_registry_file() -> RegistryFile {
+  RegistryFile {
+    schema_version: 1,
+    binaries: Vec::new(),
+  }
+}
+
+impl RegistryFile {
+  /// Adds the version to the binary with the provided name, keeping the versions sorted.
+  pub fn add_version(
+    &mut self,
+    name: &BinaryName,
+    description: &str,
+    version_info: RegistryVersionInfo,
+  ) -> Result<(), ErrBox> {
+    let binary = match self
+      .binaries
+      .iter()
+      .position(|b| b.owner == name.owner && b.name == name.name)
+    {
+      Some(index) => &mut self.binaries[index],
+      None => {
+        self.binaries.push(RegistryBinary {
+          name: name.name.clone(),
+          owner: name.owner.clone(),
+          description: description.to_string(),
+          versions: Vec::new(),
+        });
+        self.binaries.last_mut().unwrap()
+      }
+    };
+
+    if binary.versions.iter().any(|v| v.version == version_info.version) {
+      return err!(
+        "The registry file already contains version {} of {}.",
+        version_info.version,
+        name
+      );
+    }
+
+    let index = binary
+      .versions
+      .iter()
+      .position(|v| v.version > version_info.version)
+      .unwrap_or(binary.versions.len());
+    binary.versions.insert(index, version_info);
+
+    Ok(())
+  }
+
+  /// Serializes the registry file, verifying it can be read back.
+  pub fn serialize(&self) -> Result<String, ErrBox> {
+    let mut text = serde_json::to_string_pretty(&self)?;
+    text.push('\n');
+    read_registry_file(text.as_bytes())?;
+    Ok(text)
+  }
+}
@@ -28,8 +28,8 @@ impl RegistryFile {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RegistryBinary {
-  name: String,
-  owner: String,
+  pub(super) name: String,
+  pub(super) owner: String,
   pub description: String,
   pub versions: Vec<RegistryVersionInfo>,
 }
@@ -62,12 +62,16 @@ pub fn download_registry_file<'a, TEnvironment: Environment>(
   environment: &TEnvironment,
   url: &str,
 ) -> Result<RegistryFile, ErrBox> {
-  let plugin_file_bytes = environment.download_file(&url)?;
+  let registry_file_bytes = environment.fetch_url(&Url::parse(url)?)?;
+  let registry_file = read_registry_file(&registry_file_bytes)?;
 
-  read_registry_file(&plugin_file_bytes)
+  // keep the latest copy around for commands that only need to search the registry
+  super::cache_registry_file_bytes(environment, url, &registry_file_bytes)?;
+
+  Ok(registry_file)
 }
 
-fn read_registry_file(file_bytes: &[u8]) -> Result<RegistryFile, ErrBox> {
+pub fn read_registry_file(file_bytes: &[u8]) -> Result<RegistryFile, ErrBox> {
   // todo: don't use serde because this should transform up to the latest schema version
   match serde_json::from_slice::<RegistryFile>(&file_bytes) {
     Ok(file) => {
@@ -19,6 +19,10 @@ pub struct RegistryItem {
 }
 
 impl RegistryItem {
+  pub fn name(&self) -> &BinaryName {
+    &self.name
+  }
+
   pub fn compare(&self, other: &RegistryItem) -> Ordering {
     let ordering = self.name.cmp(&other.name);
     match ordering {
@@ -114,6 +118,14 @@ impl Registry {
     }
   }
 
+  /// Gets the distinct registry urls sorted alphabetically.
+  pub fn urls(&self) -> Vec<String> {
+    let mut urls = self.name_to_urls.values().flatten().cloned().collect::<Vec<_>>();
+    urls.sort();
+    urls.dedup();
+    urls
+  }
+
   pub fn items(&self) -> Vec<RegistryItem> {
     let mut results = Vec::new();
     for (name, urls) in self.name_to_urls.iter() {
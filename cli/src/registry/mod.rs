@@ -1,5 +1,11 @@
+mod cached_registry_file;
+mod create_registry_file;
 mod download_registry_file;
 mod manifest;
+mod resolve_binary_url;
 
+pub use cached_registry_file::*;
+pub use create_registry_file::*;
 pub use download_registry_file::*;
 pub use manifest::*;
+pub use resolve_binary_url::*;
@@ -0,0 +1,176 @@
+use dprint_cli_core::types::ErrBox;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use super::{download_registry_file, Registry, RegistryVersionInfo, UrlResult};
+use crate::environment::Environment;
+use crate::settings::PrereleasePolicy;
+use crate::types::{BinaryName, NameSelector, VersionSelector};
+use crate::utils::ChecksumUrl;
+
+/// Finds the url of the binary manifest file for the binary in the registries.
+///
+/// The registries added with `bvm registry add` are searched followed by the
+/// `registries` setting or only the provided registry url when one is provided.
+pub fn resolve_binary_url(
+  environment: &impl Environment,
+  name_selector: &NameSelector,
+  version_selector: Option<&VersionSelector>,
+  registry_url: Option<&str>,
+) -> Result<ChecksumUrl, ErrBox> {
+  let url_results = match registry_url {
+    Some(registry_url) => get_registry_url_results(environment, &[registry_url.to_string()], name_selector)?,
+    None => {
+      let registry = Registry::load(environment);
+      let url_results = registry.get_urls(name_selector);
+      if url_results.is_empty() {
+        get_registry_url_results(environment, &environment.get_settings().registries(), name_selector)?
+      } else {
+        url_results
+      }
+    }
+  };
+
+  if url_results.is_empty() {
+    return match registry_url {
+      Some(registry_url) => err!("Could not find binary '{}' in {}.", name_selector, registry_url),
+      None => err!("There were no registries found for the provided binary. Did you mean to add one using `bvm registry add <url>`?"),
+    };
+  }
+
+  // display an error if there are multiple owners
+  let mut binary_names = url_results
+    .iter()
+    .map(|r| &r.owner)
+    .collect::<HashSet<_>>()
+    .into_iter()
+    .map(|o| format!("{}/{}", o, name_selector.name))
+    .collect::<Vec<String>>();
+  if binary_names.len() > 1 {
+    binary_names.sort();
+    return err!(
+      "There were multiple binaries with the name '{}'. Please include the owner in the name:\n  {}",
+      name_selector.name,
+      binary_names.join("\n  ")
+    );
+  }
+  let binary_name = BinaryName::new(url_results[0].owner.clone(), name_selector.name.clone());
+
+  // now get the url
+  let urls: Vec<String> = url_results.into_iter().map(|r| r.url).collect();
+  let selected_url = if let Some(version) = &version_selector {
+    find_url(environment, &urls, &binary_name, |item| version.matches(&item.version))?
+  } else {
+    find_latest_url(environment, &urls, &binary_name)?
+  };
+  if let Some(selected_url) = selected_url {
+    Ok(selected_url)
+  } else {
+    if let Some(version) = &version_selector {
+      err!(
+        "Could not find binary '{}' matching '{}' in any registry.",
+        name_selector,
+        version
+      )
+    } else {
+      err!("Could not find binary '{}' in any registry.", name_selector)
+    }
+  }
+}
+
+fn get_registry_url_results(
+  environment: &impl Environment,
+  registry_urls: &[String],
+  name_selector: &NameSelector,
+) -> Result<Vec<UrlResult>, ErrBox> {
+  let mut results = Vec::new();
+  for url in registry_urls {
+    let registry_file = download_registry_file(environment, url)?;
+    for registry_binary in registry_file.binaries.iter() {
+      let binary_name = registry_binary.get_binary_name();
+      if name_selector.is_match(&binary_name) {
+        results.push(UrlResult {
+          owner: binary_name.owner,
+          url: url.clone(),
+        });
+      }
+    }
+  }
+  Ok(results)
+}
+
+fn find_url(
+  environment: &impl Environment,
+  urls: &[String],
+  name: &BinaryName,
+  is_match: impl Fn(&RegistryVersionInfo) -> bool,
+) -> Result<Option<ChecksumUrl>, ErrBox> {
+  let mut best_match: Option<RegistryVersionInfo> = None;
+  for url in urls.iter() {
+    let registry_file = download_registry_file(environment, url)?;
+    if let Some(registry_binary) = registry_file.take_binary_with_name(name) {
+      for version_info in registry_binary.versions {
+        if is_match(&version_info) {
+          if let Some(best_match_val) = &best_match {
+            if best_match_val.version.cmp(&version_info.version) == Ordering::Less {
+              best_match = Some(version_info);
+            }
+          } else {
+            best_match = Some(version_info);
+          }
+        }
+      }
+    }
+  }
+
+  Ok(match best_match {
+    Some(version_info) => Some(version_info.get_url()?),
+    None => None,
+  })
+}
+
+fn find_latest_url(
+  environment: &impl Environment,
+  urls: &[String],
+  name: &BinaryName,
+) -> Result<Option<ChecksumUrl>, ErrBox> {
+  let mut latest_pre_release: Option<RegistryVersionInfo> = None;
+  let mut latest_release: Option<RegistryVersionInfo> = None;
+  for url in urls.iter() {
+    let registry_file = download_registry_file(environment, url)?;
+    if let Some(registry_binary) = registry_file.take_binary_with_name(name) {
+      for item in registry_binary.versions {
+        let latest = if item.version.is_prerelease() {
+          &mut latest_pre_release
+        } else {
+          &mut latest_release
+        };
+        if let Some(latest) = latest.as_mut() {
+          if item.version.gt(&latest.version) {
+            *latest = item;
+          }
+        } else {
+          *latest = Some(item);
+        }
+      }
+    }
+  }
+
+  let latest = match environment.get_settings().prerelease() {
+    PrereleasePolicy::Never => latest_release,
+    PrereleasePolicy::Fallback => latest_release.or(latest_pre_release),
+    PrereleasePolicy::Always => match (latest_release, latest_pre_release) {
+      (Some(release), Some(pre_release)) => Some(if pre_release.version.gt(&release.version) {
+        pre_release
+      } else {
+        release
+      }),
+      (release, pre_release) => release.or(pre_release),
+    },
+  };
+
+  Ok(match latest {
+    Some(item) => Some(item.get_url()?),
+    None => None,
+  })
+}
@@ -0,0 +1,327 @@
+use dprint_cli_core::checksums::get_sha256_checksum;
+use dprint_cli_core::types::ErrBox;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+mod trusted_scripts;
+
+use trusted_scripts::TrustedScripts;
+
+use crate::environment::{Environment, ShellCommand};
+use crate::types::{BinaryName, Version};
+
+/// How many of the last lines of a failed script's output to show.
+const MAX_FAILURE_OUTPUT_LINES: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptKind {
+  PreInstall,
+  PostInstall,
+  PreUninstall,
+  PostUninstall,
+}
+
+impl ScriptKind {
+  /// The name of the script's property in binary manifest and configuration files.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ScriptKind::PreInstall => "onPreInstall",
+      ScriptKind::PostInstall => "onPostInstall",
+      ScriptKind::PreUninstall => "onPreUninstall",
+      ScriptKind::PostUninstall => "onPostUninstall",
+    }
+  }
+
+  /// Gets the flag that skips running this kind of script along with the action it skips them for.
+  fn get_skip_flag_and_action(&self) -> (&'static str, &'static str) {
+    match self {
+      ScriptKind::PreInstall | ScriptKind::PostInstall => ("--ignore-scripts", "install"),
+      ScriptKind::PreUninstall | ScriptKind::PostUninstall => ("--skip-scripts", "uninstall"),
+    }
+  }
+}
+
+/// How the scripts of binaries and configuration files are handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptsMode {
+  /// Runs approved scripts and asks about any others when bvm is interactive.
+  Run,
+  /// Runs approved scripts and fails on any others without asking. This is used
+  /// when a shim installs binaries since its stdin belongs to the command being run.
+  RunApprovedOnly,
+  /// Skips running the scripts.
+  Skip,
+}
+
+impl ScriptsMode {
+  pub fn from_skip(skip: bool) -> ScriptsMode {
+    if skip {
+      ScriptsMode::Skip
+    } else {
+      ScriptsMode::Run
+    }
+  }
+}
+
+/// What a script is being run for, which is exposed to the script in environment variables.
+pub enum ScriptTarget<'a> {
+  Binary {
+    name: &'a BinaryName,
+    version: &'a Version,
+    /// The directory the binary is installed to.
+    binary_dir: &'a Path,
+    /// The directory the binary's download is extracted to, which is only known when installing.
+    output_dir: Option<&'a Path>,
+  },
+  ConfigFile {
+    file_path: &'a Path,
+  },
+}
+
+impl<'a> ScriptTarget<'a> {
+  fn get_env_vars(&self) -> Vec<(String, String)> {
+    let mut env_vars = Vec::new();
+    match self {
+      ScriptTarget::Binary {
+        name,
+        version,
+        binary_dir,
+        output_dir,
+      } => {
+        env_vars.push(("BVM_BINARY_OWNER".to_string(), name.owner.clone()));
+        env_vars.push(("BVM_BINARY_NAME".to_string(), name.name.as_str().to_string()));
+        env_vars.push(("BVM_BINARY_VERSION".to_string(), version.as_str().to_string()));
+        env_vars.push(("BVM_BINARY_DIR".to_string(), binary_dir.to_string_lossy().to_string()));
+        if let Some(output_dir) = output_dir {
+          env_vars.push(("BVM_OUTPUT_DIR".to_string(), output_dir.to_string_lossy().to_string()));
+        }
+      }
+      ScriptTarget::ConfigFile { file_path } => {
+        if let Some(config_dir) = file_path.parent() {
+          env_vars.push(("BVM_CONFIG_DIR".to_string(), config_dir.to_string_lossy().to_string()));
+        }
+      }
+    }
+    env_vars.push(("BVM_PLATFORM".to_string(), get_platform_name().to_string()));
+    env_vars
+  }
+
+  fn get_log_file_path(&self, environment: &impl Environment, kind: ScriptKind) -> PathBuf {
+    let logs_dir = environment.get_local_user_data_dir().join("logs");
+    let dir = match self {
+      ScriptTarget::Binary { name, version, .. } => logs_dir
+        .join("binaries")
+        .join(&name.owner)
+        .join(name.name.as_str())
+        .join(version.as_str()),
+      ScriptTarget::ConfigFile { .. } => logs_dir.join("config"),
+    };
+    dir.join(format!("{}.log", kind.as_str()))
+  }
+}
+
+/// Runs a script from a binary manifest or configuration file when it's
+/// trusted, which is when it was previously approved, its source is in the
+/// `allowedScriptSources` setting, or the user approves it now.
+///
+/// The source is the url of the binary manifest file or the path of the
+/// configuration file and approvals are stored for that source along with
+/// the checksum of the script so that a changed script is asked about again.
+///
+/// The script's output is written to a log file that's shown when it fails.
+pub fn run_script(
+  environment: &impl Environment,
+  kind: ScriptKind,
+  source: &str,
+  script: &str,
+  cwd: &Path,
+  target: &ScriptTarget,
+  mode: ScriptsMode,
+) -> Result<(), ErrBox> {
+  if mode == ScriptsMode::Skip {
+    environment.log_stderr(&format!("Skipped the {} script of {}.", kind.as_str(), source));
+    return Ok(());
+  }
+
+  ensure_trusted(environment, kind, source, script, mode)?;
+
+  let shell_command = ShellCommand {
+    cwd: cwd.to_path_buf(),
+    command: script.to_string(),
+    env_vars: target.get_env_vars(),
+    log_file_path: target.get_log_file_path(environment, kind),
+    timeout: environment.get_settings().script_timeout(),
+  };
+  match environment.run_shell_command(&shell_command) {
+    Ok(()) => {
+      log_verbose!(
+        environment,
+        "Wrote the output of the {} script to {}",
+        kind.as_str(),
+        shell_command.log_file_path.display()
+      );
+      Ok(())
+    }
+    Err(err) => {
+      let output = environment
+        .read_file_text(&shell_command.log_file_path)
+        .unwrap_or_default();
+      let output_lines = output.trim_end().lines().collect::<Vec<_>>();
+      let mut message = format!("The {} script of {} failed. {}", kind.as_str(), source, err);
+      if !output_lines.is_empty() {
+        let start_index = output_lines.len().saturating_sub(MAX_FAILURE_OUTPUT_LINES);
+        message.push_str(&format!("\n\n{}", output_lines[start_index..].join("\n")));
+      }
+      message.push_str(&format!(
+        "\n\nThe output was saved to {}",
+        shell_command.log_file_path.display()
+      ));
+      err!("{}", message)
+    }
+  }
+}
+
+/// Gets if the source is the allowed source or within it. Urls must have the
+/// same origin and paths only match whole path segments, so `https://github.com/org`
+/// allows `https://github.com/org/name/bvm.json`, but not `https://github.com/org-other/...`.
+fn is_allowed_source(source: &str, allowed_source: &str) -> bool {
+  if source == allowed_source {
+    return true;
+  }
+  let urls = (Url::parse(source), Url::parse(allowed_source));
+  if let (Ok(url), Ok(allowed_url)) = urls {
+    if url.host().is_some() && allowed_url.host().is_some() {
+      let allowed_path = allowed_url.path().trim_end_matches('/');
+      return url.origin() == allowed_url.origin()
+        && (url.path() == allowed_path || url.path().starts_with(&format!("{}/", allowed_path)));
+    }
+  }
+  // configuration file paths
+  Path::new(source).starts_with(allowed_source)
+}
+
+fn ensure_trusted(
+  environment: &impl Environment,
+  kind: ScriptKind,
+  source: &str,
+  script: &str,
+  mode: ScriptsMode,
+) -> Result<(), ErrBox> {
+  let (skip_flag, action) = kind.get_skip_flag_and_action();
+  let checksum = get_sha256_checksum(script.as_bytes());
+  let mut trusted_scripts = TrustedScripts::load(environment);
+  if trusted_scripts.contains(source, &checksum) {
+    return Ok(());
+  }
+  let settings = environment.get_settings();
+  if settings
+    .allowed_script_sources()
+    .iter()
+    .any(|allowed_source| is_allowed_source(source, allowed_source))
+  {
+    return Ok(());
+  }
+
+  if mode == ScriptsMode::RunApprovedOnly {
+    return err!(
+      concat!(
+        "The {} script of {} has not been approved to run:\n  {}\n\n",
+        "Binaries are not installed with unapproved scripts when running a command. Run `bvm install` ",
+        "to approve it or add the source to the allowedScriptSources setting."
+      ),
+      kind.as_str(),
+      source,
+      script
+    );
+  }
+  if !environment.is_interactive() || is_ci(environment) {
+    return err!(
+      concat!(
+        "The {} script of {} has not been approved to run:\n  {}\n\n",
+        "Approve it by running bvm in an interactive terminal, add the source to the ",
+        "allowedScriptSources setting, or provide {} to {} without running scripts."
+      ),
+      kind.as_str(),
+      source,
+      script,
+      skip_flag,
+      action
+    );
+  }
+
+  let prompt = format!(
+    "{} wants to run its {} script:\n  {}\nRun it? Approved scripts are not asked about again unless they change.",
+    source,
+    kind.as_str(),
+    script
+  );
+  if environment.confirm(&prompt)? {
+    trusted_scripts.add(source.to_string(), checksum);
+    trusted_scripts.save(environment)
+  } else {
+    err!(
+      "The {} script of {} was not approved. Provide {} to {} without running scripts.",
+      kind.as_str(),
+      source,
+      skip_flag,
+      action
+    )
+  }
+}
+
+fn get_platform_name() -> &'static str {
+  if cfg!(target_os = "windows") {
+    "windows"
+  } else if cfg!(target_os = "macos") {
+    "mac"
+  } else {
+    "linux"
+  }
+}
+
+fn is_ci(environment: &impl Environment) -> bool {
+  match environment.get_env_var("CI") {
+    Some(value) => !matches!(value.as_str(), "" | "0" | "false"),
+    None => false,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn is_allowed_source_matches_whole_segments() {
+    assert!(is_allowed_source(
+      "https://github.com/org/name/bvm.json",
+      "https://github.com/org"
+    ));
+    assert!(is_allowed_source(
+      "https://github.com/org/name/bvm.json",
+      "https://github.com/org/"
+    ));
+    assert!(is_allowed_source("https://github.com/org", "https://github.com/org"));
+    assert!(is_allowed_source("https://example.com/bvm.json", "https://example.com"));
+    assert!(!is_allowed_source(
+      "https://github.com/org-evil/bvm.json",
+      "https://github.com/org"
+    ));
+    assert!(!is_allowed_source(
+      "https://github.com/org-evil/bvm.json",
+      "https://github.com/org-"
+    ));
+    assert!(!is_allowed_source(
+      "https://example.com.attacker.net/bvm.json",
+      "https://example.com"
+    ));
+    assert!(!is_allowed_source(
+      "https://example.com:8080/bvm.json",
+      "https://example.com"
+    ));
+    assert!(!is_allowed_source("http://example.com/bvm.json", "https://example.com"));
+
+    assert!(is_allowed_source("/project/sub/bvm.json", "/project"));
+    assert!(is_allowed_source("/project/sub/bvm.json", "/project/"));
+    assert!(!is_allowed_source("/project-evil/bvm.json", "/project"));
+  }
+}
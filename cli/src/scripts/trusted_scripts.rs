@@ -0,0 +1,62 @@
+use dprint_cli_core::types::ErrBox;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::environment::Environment;
+
+/// Scripts the user approved to run, which are stored in the user data directory.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedScripts {
+  scripts: Vec<TrustedScript>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct TrustedScript {
+  /// The url of the binary manifest file or path of the configuration file.
+  source: String,
+  /// The sha256 checksum of the script's text.
+  checksum: String,
+}
+
+impl TrustedScripts {
+  pub fn load(environment: &impl Environment) -> TrustedScripts {
+    let file_path = get_trusted_scripts_file_path(environment);
+    match environment.read_file_text(&file_path) {
+      Ok(text) => match serde_json::from_str(&text) {
+        Ok(trusted_scripts) => trusted_scripts,
+        Err(err) => {
+          environment.log_stderr(&format!("Error deserializing trusted scripts: {}", err));
+          TrustedScripts::default()
+        }
+      },
+      Err(_) => TrustedScripts::default(),
+    }
+  }
+
+  pub fn save(&self, environment: &impl Environment) -> Result<(), ErrBox> {
+    let file_path = get_trusted_scripts_file_path(environment);
+    let serialized_scripts = serde_json::to_string_pretty(&self)?;
+    environment.write_file_text(&file_path, &serialized_scripts)
+  }
+
+  pub fn contains(&self, source: &str, checksum: &str) -> bool {
+    self
+      .scripts
+      .iter()
+      .any(|script| script.source == source && script.checksum == checksum)
+  }
+
+  pub fn add(&mut self, source: String, checksum: String) {
+    let script = TrustedScript { source, checksum };
+    if !self.scripts.contains(&script) {
+      self.scripts.push(script);
+    }
+  }
+}
+
+fn get_trusted_scripts_file_path(environment: &impl Environment) -> PathBuf {
+  let user_data_dir = environment.get_user_data_dir(); // share across domains
+  user_data_dir.join("trusted-scripts.json")
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use url::Url;
+
+use crate::environment::Environment;
+
+/// Credentials sent in the authorization header of requests to a host.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Credential {
+  Token { token: String },
+  Basic { username: String, password: String },
+}
+
+impl Credential {
+  pub fn get_authorization_header_value(&self) -> String {
+    match self {
+      Credential::Token { token } => format!("Bearer {}", token),
+      Credential::Basic { username, password } => {
+        format!("Basic {}", base64::encode(format!("{}:{}", username, password)))
+      }
+    }
+  }
+}
+
+/// Gets the credential for the url's host from (in order of precedence) the
+/// `BVM_AUTH_TOKENS` environment variable, the credentials file in the user
+/// data directory, or the netrc file.
+pub fn get_credential_for_url(environment: &impl Environment, url: &Url) -> Option<Credential> {
+  let host = url.host_str()?.to_lowercase();
+  let host_and_port = url.port().map(|port| format!("{}:{}", host, port));
+  let matches_host = |value: &str| {
+    let value = value.to_lowercase();
+    value == host || Some(&value) == host_and_port.as_ref()
+  };
+
+  if let Some(credential) = get_env_var_credential(environment, &matches_host) {
+    log_verbose!(environment, "Using credentials for {} from BVM_AUTH_TOKENS.", host);
+    return Some(credential);
+  }
+  if let Some(credential) = get_credentials_file_credential(environment, &matches_host) {
+    log_verbose!(environment, "Using credentials for {} from the credentials file.", host);
+    return Some(credential);
+  }
+  if let Some(credential) = get_netrc_credential(environment, &matches_host) {
+    log_verbose!(environment, "Using credentials for {} from the netrc file.", host);
+    return Some(credential);
+  }
+  None
+}
+
+/// Parses a semi-colon separated list of `<token>@<host>` or
+/// `<username>:<password>@<host>` entries.
+fn get_env_var_credential(environment: &impl Environment, matches_host: &impl Fn(&str) -> bool) -> Option<Credential> {
+  let text = environment.get_env_var("BVM_AUTH_TOKENS")?;
+  for entry in text
+    .split(';')
+    .map(|entry| entry.trim())
+    .filter(|entry| !entry.is_empty())
+  {
+    let index = match entry.rfind('@') {
+      Some(index) => index,
+      None => {
+        environment.log_stderr("Ignoring an entry in BVM_AUTH_TOKENS that was not in the format <token>@<host>.");
+        continue;
+      }
+    };
+    if !matches_host(&entry[index + 1..]) {
+      continue;
+    }
+    let value = &entry[..index];
+    return Some(match value.find(':') {
+      Some(index) => Credential::Basic {
+        username: value[..index].to_string(),
+        password: value[index + 1..].to_string(),
+      },
+      None => Credential::Token {
+        token: value.to_string(),
+      },
+    });
+  }
+  None
+}
+
+fn get_credentials_file_credential(
+  environment: &impl Environment,
+  matches_host: &impl Fn(&str) -> bool,
+) -> Option<Credential> {
+  let file_path = get_credentials_file_path(environment);
+  let text = environment.read_file_text(&file_path).ok()?;
+  match serde_json::from_str::<HashMap<String, Credential>>(&text) {
+    Ok(credentials) => credentials
+      .into_iter()
+      .find(|(host, _)| matches_host(host))
+      .map(|(_, credential)| credential),
+    Err(err) => {
+      environment.log_stderr(&format!("Error deserializing {}: {}", file_path.display(), err));
+      None
+    }
+  }
+}
+
+/// Gets the login and password of the netrc entry for the host. Default
+/// entries are ignored so credentials are only sent to matching hosts.
+fn get_netrc_credential(environment: &impl Environment, matches_host: &impl Fn(&str) -> bool) -> Option<Credential> {
+  let file_path = get_netrc_file_path(environment)?;
+  let text = environment.read_file_text(&file_path).ok()?;
+  let mut tokens = get_netrc_tokens(&text).into_iter();
+  let mut is_matching_entry = false;
+  let mut login = None;
+  let mut password = None;
+
+  loop {
+    let token = tokens.next();
+    if matches!(token, None | Some("machine") | Some("default") | Some("macdef")) {
+      if is_matching_entry {
+        if let (Some(username), Some(password)) = (login.take(), password.take()) {
+          return Some(Credential::Basic { username, password });
+        }
+      }
+      is_matching_entry = false;
+      login = None;
+      password = None;
+    }
+
+    match token {
+      None => return None,
+      Some("machine") => is_matching_entry = tokens.next().map(matches_host).unwrap_or(false),
+      Some("login") => login = tokens.next().map(String::from),
+      Some("password") => password = tokens.next().map(String::from),
+      _ => {}
+    }
+  }
+}
+
+/// Splits the netrc file into tokens excluding the bodies of macro
+/// definitions, which end at a blank line.
+fn get_netrc_tokens(text: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let mut is_in_macro = false;
+  for line in text.lines() {
+    if is_in_macro {
+      is_in_macro = !line.trim().is_empty();
+      continue;
+    }
+    for token in line.split_whitespace() {
+      tokens.push(token);
+      if token == "macdef" {
+        // the rest of the line is the macro's name
+        is_in_macro = true;
+        break;
+      }
+    }
+  }
+  tokens
+}
+
+fn get_netrc_file_path(environment: &impl Environment) -> Option<PathBuf> {
+  if let Some(file_path) = environment.get_env_var("NETRC") {
+    return Some(PathBuf::from(file_path));
+  }
+  let home_dir = environment.get_home_dir()?;
+  Some(home_dir.join(if cfg!(windows) { "_netrc" } else { ".netrc" }))
+}
+
+fn get_credentials_file_path(environment: &impl Environment) -> PathBuf {
+  environment.get_user_data_dir().join("credentials.json")
+}
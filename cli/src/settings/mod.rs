@@ -0,0 +1,5 @@
+mod credentials;
+mod user_settings;
+
+pub use credentials::*;
+pub use user_settings::*;
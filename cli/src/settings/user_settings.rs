@@ -0,0 +1,533 @@
+use dprint_cli_core::types::ErrBox;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+use crate::environment::Environment;
+use crate::plugins::verify_env_var_name;
+
+const BYTES_PER_MEGABYTE: u64 = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingKey {
+  Registries,
+  AutoInstall,
+  Offline,
+  Prerelease,
+  LogLevel,
+  Mirrors,
+  ConnectTimeout,
+  ReadTimeout,
+  DownloadRetries,
+  AllowedEnvVars,
+  AllowedScriptSources,
+  ScriptTimeout,
+  DownloadParallelism,
+  CacheSize,
+}
+
+const ALL_SETTING_KEYS: [SettingKey; 14] = [
+  SettingKey::Registries,
+  SettingKey::AutoInstall,
+  SettingKey::Offline,
+  SettingKey::Prerelease,
+  SettingKey::LogLevel,
+  SettingKey::Mirrors,
+  SettingKey::ConnectTimeout,
+  SettingKey::ReadTimeout,
+  SettingKey::DownloadRetries,
+  SettingKey::AllowedEnvVars,
+  SettingKey::AllowedScriptSources,
+  SettingKey::ScriptTimeout,
+  SettingKey::DownloadParallelism,
+  SettingKey::CacheSize,
+];
+
+impl SettingKey {
+  pub fn all() -> &'static [SettingKey] {
+    &ALL_SETTING_KEYS
+  }
+
+  pub fn parse(text: &str) -> Result<SettingKey, ErrBox> {
+    match SettingKey::all().iter().find(|key| key.as_str() == text) {
+      Some(key) => Ok(*key),
+      None => err!(
+        "Unknown setting '{}'. Expected one of the following: {}",
+        text,
+        SettingKey::all()
+          .iter()
+          .map(|key| key.as_str())
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SettingKey::Registries => "registries",
+      SettingKey::AutoInstall => "autoInstall",
+      SettingKey::Offline => "offline",
+      SettingKey::Prerelease => "prerelease",
+      SettingKey::LogLevel => "logLevel",
+      SettingKey::Mirrors => "mirrors",
+      SettingKey::ConnectTimeout => "connectTimeout",
+      SettingKey::ReadTimeout => "readTimeout",
+      SettingKey::DownloadRetries => "downloadRetries",
+      SettingKey::AllowedEnvVars => "allowedEnvVars",
+      SettingKey::AllowedScriptSources => "allowedScriptSources",
+      SettingKey::ScriptTimeout => "scriptTimeout",
+      SettingKey::DownloadParallelism => "downloadParallelism",
+      SettingKey::CacheSize => "cacheSize",
+    }
+  }
+
+  /// The environment variable that overrides the value in the settings file.
+  pub fn env_var_name(&self) -> &'static str {
+    match self {
+      SettingKey::Registries => "BVM_REGISTRIES",
+      SettingKey::AutoInstall => "BVM_AUTO_INSTALL",
+      SettingKey::Offline => "BVM_OFFLINE",
+      SettingKey::Prerelease => "BVM_PRERELEASE",
+      SettingKey::LogLevel => "BVM_LOG_LEVEL",
+      SettingKey::Mirrors => "BVM_MIRRORS",
+      SettingKey::ConnectTimeout => "BVM_CONNECT_TIMEOUT",
+      SettingKey::ReadTimeout => "BVM_READ_TIMEOUT",
+      SettingKey::DownloadRetries => "BVM_DOWNLOAD_RETRIES",
+      SettingKey::AllowedEnvVars => "BVM_ALLOWED_ENV_VARS",
+      SettingKey::AllowedScriptSources => "BVM_ALLOWED_SCRIPT_SOURCES",
+      SettingKey::ScriptTimeout => "BVM_SCRIPT_TIMEOUT",
+      SettingKey::DownloadParallelism => "BVM_DOWNLOAD_PARALLELISM",
+      SettingKey::CacheSize => "BVM_CACHE_SIZE",
+    }
+  }
+}
+
+impl std::fmt::Display for SettingKey {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// How pre-release versions are treated when selecting the latest version from a registry.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PrereleasePolicy {
+  /// Never select a pre-release version.
+  Never,
+  /// Only select a pre-release version when there are no releases.
+  Fallback,
+  /// Select the latest version regardless of whether it's a pre-release.
+  Always,
+}
+
+impl PrereleasePolicy {
+  fn parse(text: &str) -> Result<PrereleasePolicy, ErrBox> {
+    Ok(match text {
+      "never" => PrereleasePolicy::Never,
+      "fallback" => PrereleasePolicy::Fallback,
+      "always" => PrereleasePolicy::Always,
+      _ => return err!("Expected 'never', 'fallback', or 'always', but found '{}'.", text),
+    })
+  }
+
+  fn as_str(&self) -> &'static str {
+    match self {
+      PrereleasePolicy::Never => "never",
+      PrereleasePolicy::Fallback => "fallback",
+      PrereleasePolicy::Always => "always",
+    }
+  }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+  /// Do not display progress bars.
+  Quiet,
+  Info,
+  /// Log diagnostic information about what bvm is doing.
+  Verbose,
+}
+
+impl LogLevel {
+  fn parse(text: &str) -> Result<LogLevel, ErrBox> {
+    Ok(match text {
+      "quiet" => LogLevel::Quiet,
+      "info" => LogLevel::Info,
+      "verbose" => LogLevel::Verbose,
+      _ => return err!("Expected 'quiet', 'info', or 'verbose', but found '{}'.", text),
+    })
+  }
+
+  fn as_str(&self) -> &'static str {
+    match self {
+      LogLevel::Quiet => "quiet",
+      LogLevel::Info => "info",
+      LogLevel::Verbose => "verbose",
+    }
+  }
+}
+
+/// Rewrites urls starting with the prefix to each replacement in order.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorRule {
+  pub prefix: String,
+  pub replacements: Vec<String>,
+}
+
+impl MirrorRule {
+  fn parse(text: &str) -> Result<MirrorRule, ErrBox> {
+    let (prefix, replacements) = match text.find('=') {
+      Some(index) => (text[..index].trim(), &text[index + 1..]),
+      None => {
+        return err!(
+          "Expected a rule in the format <prefix>=<replacement>[|<replacement>...], but found '{}'.",
+          text
+        )
+      }
+    };
+    let prefix = parse_url(prefix)?;
+    let mut rule = MirrorRule {
+      prefix,
+      replacements: Vec::new(),
+    };
+    for replacement in replacements.split('|').map(|r| r.trim()).filter(|r| !r.is_empty()) {
+      rule.replacements.push(parse_url(replacement)?);
+    }
+    if rule.replacements.is_empty() {
+      return err!("Expected at least one replacement for the prefix '{}'.", rule.prefix);
+    }
+    Ok(rule)
+  }
+
+  fn as_text(&self) -> String {
+    format!("{}={}", self.prefix, self.replacements.join("|"))
+  }
+
+  /// Gets if the url starts with the prefix on a path segment boundary, so
+  /// `https://example.com/a` matches `https://example.com/a/b`, but not
+  /// `https://example.com/ab` or `https://example.com.evil/a`.
+  fn is_match(&self, url: &str) -> bool {
+    match url.strip_prefix(&self.prefix) {
+      Some(rest) => self.prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+      None => false,
+    }
+  }
+}
+
+/// User level settings stored in the user data directory.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  registries: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  auto_install: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  offline: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  prerelease: Option<PrereleasePolicy>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  log_level: Option<LogLevel>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  mirrors: Option<Vec<MirrorRule>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  connect_timeout: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  read_timeout: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  download_retries: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  allowed_env_vars: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  allowed_script_sources: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  script_timeout: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  download_parallelism: Option<usize>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  cache_size: Option<u64>,
+  /// Keys whose values came from an environment variable.
+  #[serde(skip)]
+  env_overrides: Vec<SettingKey>,
+}
+
+impl Settings {
+  /// Loads the settings file and applies any `BVM_<KEY>` environment variable overrides.
+  pub fn load(environment: &impl Environment) -> Settings {
+    let mut settings = Settings::load_file(environment);
+    settings.apply_env_overrides(environment);
+    settings
+  }
+
+  /// Loads only what's stored in the settings file.
+  pub fn load_file(environment: &impl Environment) -> Settings {
+    let file_path = get_settings_file_path(environment);
+    match environment.read_file_text(&file_path) {
+      Ok(text) => match serde_json::from_str(&text) {
+        Ok(settings) => settings,
+        Err(err) => {
+          environment.log_stderr(&format!("Error deserializing settings: {}", err));
+          Settings::default()
+        }
+      },
+      Err(_) => Settings::default(),
+    }
+  }
+
+  pub fn save(&self, environment: &impl Environment) -> Result<(), ErrBox> {
+    if !self.env_overrides.is_empty() {
+      return err!(
+        "Cannot save the settings because they include values from the following environment variables: {}",
+        self
+          .env_overrides
+          .iter()
+          .map(|key| key.env_var_name())
+          .collect::<Vec<_>>()
+          .join(", ")
+      );
+    }
+
+    let file_path = get_settings_file_path(environment);
+    let serialized_settings = serde_json::to_string_pretty(&self)?;
+    environment.write_file_text(&file_path, &serialized_settings)?;
+    Ok(())
+  }
+
+  fn apply_env_overrides(&mut self, environment: &impl Environment) {
+    for key in SettingKey::all() {
+      if let Some(value) = environment.get_env_var(key.env_var_name()) {
+        match self.set_value(*key, &value) {
+          Ok(()) => self.env_overrides.push(*key),
+          Err(err) => environment.log_stderr(&format!(
+            "Ignoring the {} environment variable. {}",
+            key.env_var_name(),
+            err
+          )),
+        }
+      }
+    }
+  }
+
+  /// Gets if the value of the provided key came from an environment variable.
+  pub fn is_env_override(&self, key: SettingKey) -> bool {
+    self.env_overrides.contains(&key)
+  }
+
+  /// Parses and validates the text, then sets it as the value of the key.
+  pub fn set_value(&mut self, key: SettingKey, text: &str) -> Result<(), ErrBox> {
+    let text = text.trim();
+    let result = match key {
+      SettingKey::Registries => parse_url_list(text).map(|urls| self.registries = Some(urls)),
+      SettingKey::AutoInstall => parse_bool(text).map(|value| self.auto_install = Some(value)),
+      SettingKey::Offline => parse_bool(text).map(|value| self.offline = Some(value)),
+      SettingKey::Prerelease => PrereleasePolicy::parse(text).map(|value| self.prerelease = Some(value)),
+      SettingKey::LogLevel => LogLevel::parse(text).map(|value| self.log_level = Some(value)),
+      SettingKey::Mirrors => parse_mirror_rules(text).map(|rules| self.mirrors = Some(rules)),
+      SettingKey::ConnectTimeout => parse_number(text).map(|value| self.connect_timeout = Some(value)),
+      SettingKey::ReadTimeout => parse_number(text).map(|value| self.read_timeout = Some(value)),
+      SettingKey::DownloadRetries => parse_number(text).map(|value| self.download_retries = Some(value)),
+      SettingKey::AllowedEnvVars => parse_env_var_names(text).map(|names| self.allowed_env_vars = Some(names)),
+      SettingKey::AllowedScriptSources => {
+        self.allowed_script_sources = Some(parse_list(text));
+        Ok(())
+      }
+      SettingKey::ScriptTimeout => parse_number(text).map(|value| self.script_timeout = Some(value)),
+      SettingKey::DownloadParallelism => {
+        parse_positive_number(text).map(|value| self.download_parallelism = Some(value))
+      }
+      SettingKey::CacheSize => parse_number(text).map(|value| self.cache_size = Some(value)),
+    };
+    match result {
+      Ok(()) => Ok(()),
+      Err(err) => err!("Invalid value for '{}'. {}", key, err.to_string()),
+    }
+  }
+
+  /// Gets the text of the value for the key, falling back to the default value.
+  pub fn get_value_text(&self, key: SettingKey) -> String {
+    match key {
+      SettingKey::Registries => self.registries().join(","),
+      SettingKey::AutoInstall => self.auto_install().to_string(),
+      SettingKey::Offline => self.offline().to_string(),
+      SettingKey::Prerelease => self.prerelease().as_str().to_string(),
+      SettingKey::LogLevel => self.log_level().as_str().to_string(),
+      SettingKey::Mirrors => self
+        .mirrors()
+        .iter()
+        .map(|rule| rule.as_text())
+        .collect::<Vec<_>>()
+        .join(","),
+      SettingKey::ConnectTimeout => self.connect_timeout().as_secs().to_string(),
+      SettingKey::ReadTimeout => self.read_timeout().as_secs().to_string(),
+      SettingKey::DownloadRetries => self.download_retries().to_string(),
+      SettingKey::AllowedEnvVars => self.allowed_env_vars().join(","),
+      SettingKey::AllowedScriptSources => self.allowed_script_sources().join(","),
+      SettingKey::ScriptTimeout => self
+        .script_timeout()
+        .map(|timeout| timeout.as_secs())
+        .unwrap_or(0)
+        .to_string(),
+      SettingKey::DownloadParallelism => self.download_parallelism().to_string(),
+      SettingKey::CacheSize => (self.cache_size() / BYTES_PER_MEGABYTE).to_string(),
+    }
+  }
+
+  /// Registry urls to search when a binary name isn't associated with a registry.
+  pub fn registries(&self) -> Vec<String> {
+    self.registries.clone().unwrap_or_default()
+  }
+
+  /// Whether to install a configuration file's binaries when resolving a command.
+  pub fn auto_install(&self) -> bool {
+    self.auto_install.unwrap_or(false)
+  }
+
+  /// Whether remote urls are not allowed to be downloaded.
+  pub fn offline(&self) -> bool {
+    self.offline.unwrap_or(false)
+  }
+
+  pub fn prerelease(&self) -> PrereleasePolicy {
+    self.prerelease.unwrap_or(PrereleasePolicy::Fallback)
+  }
+
+  pub fn log_level(&self) -> LogLevel {
+    self.log_level.unwrap_or(LogLevel::Info)
+  }
+
+  /// Rules for downloading remote urls from mirrors.
+  pub fn mirrors(&self) -> Vec<MirrorRule> {
+    self.mirrors.clone().unwrap_or_default()
+  }
+
+  /// How long to wait when connecting to a remote host.
+  pub fn connect_timeout(&self) -> Duration {
+    Duration::from_secs(self.connect_timeout.unwrap_or(30))
+  }
+
+  /// How long to wait for data when downloading from a remote host.
+  pub fn read_timeout(&self) -> Duration {
+    Duration::from_secs(self.read_timeout.unwrap_or(60))
+  }
+
+  /// How many times to retry a download that failed with a connection or server error.
+  pub fn download_retries(&self) -> u32 {
+    self.download_retries.unwrap_or(3)
+  }
+
+  /// Restricted environment variables that binaries are allowed to set.
+  pub fn allowed_env_vars(&self) -> Vec<String> {
+    self.allowed_env_vars.clone().unwrap_or_default()
+  }
+
+  /// Url or config file path prefixes whose install scripts may run without being approved.
+  pub fn allowed_script_sources(&self) -> Vec<String> {
+    self.allowed_script_sources.clone().unwrap_or_default()
+  }
+
+  /// How long to wait for an install script before killing it or None to wait forever.
+  pub fn script_timeout(&self) -> Option<Duration> {
+    match self.script_timeout.unwrap_or(600) {
+      0 => None,
+      secs => Some(Duration::from_secs(secs)),
+    }
+  }
+
+  /// How many archives to download at the same time when installing a configuration file's binaries.
+  pub fn download_parallelism(&self) -> usize {
+    self.download_parallelism.unwrap_or(4)
+  }
+
+  /// The maximum number of bytes of unfinished downloads to keep for resuming later.
+  pub fn cache_size(&self) -> u64 {
+    self.cache_size.unwrap_or(500).saturating_mul(BYTES_PER_MEGABYTE)
+  }
+
+  /// Gets the urls to download the provided url from in order based on the
+  /// first matching mirror rule or an empty collection when no rule matches.
+  ///
+  /// Mirrors are exclusive, so the original url is only tried when it's one
+  /// of the rule's replacements.
+  pub fn get_mirror_urls(&self, url: &str) -> Vec<String> {
+    match self.mirrors().into_iter().find(|rule| rule.is_match(url)) {
+      Some(rule) => rule
+        .replacements
+        .iter()
+        .map(|replacement| format!("{}{}", replacement, &url[rule.prefix.len()..]))
+        .collect(),
+      None => Vec::new(),
+    }
+  }
+}
+
+fn parse_bool(text: &str) -> Result<bool, ErrBox> {
+  match text {
+    "true" => Ok(true),
+    "false" => Ok(false),
+    _ => err!("Expected 'true' or 'false', but found '{}'.", text),
+  }
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, ErrBox> {
+  match text.parse::<T>() {
+    Ok(value) => Ok(value),
+    Err(_) => err!("Expected a non-negative whole number, but found '{}'.", text),
+  }
+}
+
+fn parse_positive_number<T: std::str::FromStr + PartialOrd + Default>(text: &str) -> Result<T, ErrBox> {
+  match text.parse::<T>() {
+    Ok(value) if value > T::default() => Ok(value),
+    _ => err!("Expected a whole number greater than zero, but found '{}'.", text),
+  }
+}
+
+fn parse_url_list(text: &str) -> Result<Vec<String>, ErrBox> {
+  let mut urls = Vec::new();
+  for url in text.split(',').map(|url| url.trim()).filter(|url| !url.is_empty()) {
+    urls.push(parse_url(url)?);
+  }
+  Ok(urls)
+}
+
+fn parse_list(text: &str) -> Vec<String> {
+  text
+    .split(',')
+    .map(|value| value.trim())
+    .filter(|value| !value.is_empty())
+    .map(String::from)
+    .collect()
+}
+
+fn parse_env_var_names(text: &str) -> Result<Vec<String>, ErrBox> {
+  let mut names = Vec::new();
+  for name in text.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+    verify_env_var_name(name)?;
+    names.push(name.to_string());
+  }
+  Ok(names)
+}
+
+fn parse_mirror_rules(text: &str) -> Result<Vec<MirrorRule>, ErrBox> {
+  let mut rules = Vec::new();
+  for rule in text.split(',').map(|rule| rule.trim()).filter(|rule| !rule.is_empty()) {
+    rules.push(MirrorRule::parse(rule)?);
+  }
+  Ok(rules)
+}
+
+fn parse_url(text: &str) -> Result<String, ErrBox> {
+  if let Err(err) = Url::parse(text) {
+    return err!("Could not parse '{}' as a url. {}", text, err.to_string());
+  }
+  Ok(text.to_string())
+}
+
+fn get_settings_file_path(environment: &impl Environment) -> PathBuf {
+  let user_data_dir = environment.get_user_data_dir(); // share across domains
+  user_data_dir.join("settings.json")
+}
@@ -1,5 +1,6 @@
 use super::{BvmrcBuilder, PluginFileBuilder};
 use crate::environment::{Environment, TestEnvironment, SYS_PATH_DELIMITER};
+use crate::plugins::PluginDependency;
 
 use std::io::Write;
 use std::path::PathBuf;
@@ -114,12 +115,40 @@ impl EnvironmentBuilder {
     BvmrcBuilder::new(&self.environment)
   }
 
+  /// Creates a zip file at the url with the provided file paths and unix permissions.
+  pub fn create_remote_zip_file(&self, url: impl AsRef<str>, files: Vec<(&str, u32)>) -> String {
+    let w = std::io::Cursor::new(Vec::new());
+    let mut zip = zip::ZipWriter::new(w);
+    for (file_path, mode) in files {
+      let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .unix_permissions(mode);
+      zip.start_file(file_path, options).unwrap();
+      zip.write_all(format!("test-{}", file_path).as_bytes()).unwrap();
+    }
+    let result = zip.finish().unwrap().into_inner();
+    let checksum = dprint_cli_core::checksums::get_sha256_checksum(&result);
+    self.environment.add_remote_file(url, result);
+    checksum
+  }
+
   pub fn create_remote_registry_file(
     &self,
     url: impl AsRef<str>,
     owner: impl AsRef<str>,
     name: impl AsRef<str>,
     items: Vec<crate::registry::RegistryVersionInfo>,
+  ) {
+    self.create_remote_registry_file_with_description(url, owner, name, "Some description.", items);
+  }
+
+  pub fn create_remote_registry_file_with_description(
+    &self,
+    url: impl AsRef<str>,
+    owner: impl AsRef<str>,
+    name: impl AsRef<str>,
+    description: impl AsRef<str>,
+    items: Vec<crate::registry::RegistryVersionInfo>,
   ) {
     let file_text = format!(
       r#"{{
@@ -127,12 +156,13 @@ impl EnvironmentBuilder {
     "binaries": [{{
         "owner": "{}",
         "name": "{}",
-        "description": "Some description.",
+        "description": "{}",
         "versions": [{}]
     }}]
 }}"#,
       owner.as_ref(),
       name.as_ref(),
+      description.as_ref(),
       items
         .into_iter()
         .map(|item| format!(
@@ -215,6 +245,11 @@ impl PluginBuilder {
     self
   }
 
+  pub fn add_dependency(&mut self, dependency: PluginDependency) -> &mut PluginBuilder {
+    self.file.add_dependency(dependency);
+    self
+  }
+
   pub fn add_env_path<'a>(&'a mut self, value: impl AsRef<str>) -> &'a mut PluginBuilder {
     self.file.windows().add_env_path(&value);
     self.file.linux().add_env_path(&value);
@@ -229,6 +264,18 @@ impl PluginBuilder {
     self
   }
 
+  /// Adds the environment variable for linux and mac, which allow values that windows doesn't.
+  pub fn add_unix_env_var(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> &mut PluginBuilder {
+    self.file.linux().add_env_var(&key, &value);
+    self.file.mac().add_env_var(key, value);
+    self
+  }
+
+  pub fn add_windows_env_var(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> &mut PluginBuilder {
+    self.file.windows().add_env_var(key, value);
+    self
+  }
+
   pub fn on_pre_install<'a>(&'a mut self, value: impl AsRef<str>) -> &'a mut PluginBuilder {
     self.file.windows().on_pre_install(&value);
     self.file.linux().on_pre_install(&value);
@@ -243,6 +290,20 @@ impl PluginBuilder {
     self
   }
 
+  pub fn on_pre_uninstall(&mut self, value: impl AsRef<str>) -> &mut PluginBuilder {
+    self.file.windows().on_pre_uninstall(&value);
+    self.file.linux().on_pre_uninstall(&value);
+    self.file.mac().on_pre_uninstall(value);
+    self
+  }
+
+  pub fn on_post_uninstall(&mut self, value: impl AsRef<str>) -> &mut PluginBuilder {
+    self.file.windows().on_post_uninstall(&value);
+    self.file.linux().on_post_uninstall(&value);
+    self.file.mac().on_post_uninstall(value);
+    self
+  }
+
   pub fn add_command<'a>(&'a mut self, name: impl AsRef<str>) -> &'a mut PluginBuilder {
     assert_eq!(
       self.download_type.is_some(),
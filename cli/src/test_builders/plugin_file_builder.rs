@@ -1,4 +1,4 @@
-use crate::plugins::{BinaryEnvironment, PlatformInfo, PlatformInfoCommand, SerializedPluginFile};
+use crate::plugins::{BinaryEnvironment, PlatformInfo, PlatformInfoCommand, PluginDependency, SerializedPluginFile};
 use crate::types::{CommandName, Version};
 use std::collections::HashMap;
 
@@ -21,6 +21,7 @@ impl PluginFileBuilder {
         linux: None,
         mac: None,
         windows: None,
+        dependencies: Vec::new(),
       },
       windows: None,
       linux: None,
@@ -78,6 +79,11 @@ impl PluginFileBuilder {
     self
   }
 
+  pub fn add_dependency(&mut self, dependency: PluginDependency) -> &mut PluginFileBuilder {
+    self.file.dependencies.push(dependency);
+    self
+  }
+
   pub fn windows<'a>(&'a mut self) -> &'a mut PlatformInfoBuilder {
     if self.windows.is_none() {
       self.windows = Some(PlatformInfoBuilder::new());
@@ -115,6 +121,8 @@ impl PlatformInfoBuilder {
         output_dir: None,
         on_pre_install: None,
         on_post_install: None,
+        on_pre_uninstall: None,
+        on_post_uninstall: None,
         environment: None,
       },
     }
@@ -166,6 +174,16 @@ impl PlatformInfoBuilder {
     self
   }
 
+  pub fn on_pre_uninstall(&mut self, value: impl AsRef<str>) -> &mut PlatformInfoBuilder {
+    self.info.on_pre_uninstall = Some(value.as_ref().to_string());
+    self
+  }
+
+  pub fn on_post_uninstall(&mut self, value: impl AsRef<str>) -> &mut PlatformInfoBuilder {
+    self.info.on_post_uninstall = Some(value.as_ref().to_string());
+    self
+  }
+
   pub fn add_env_path<'a>(&'a mut self, value: impl AsRef<str>) -> &'a mut PlatformInfoBuilder {
     self.ensure_environment();
     self
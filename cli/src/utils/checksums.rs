@@ -0,0 +1,30 @@
+use dprint_cli_core::types::ErrBox;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+/// Gets the sha256 checksum of the reader's bytes without holding them all in memory.
+pub fn get_sha256_checksum_from_reader(reader: &mut impl Read) -> Result<String, ErrBox> {
+  let mut hasher = Sha256::new();
+  let mut buf = [0; 8192];
+  loop {
+    let bytes_read = reader.read(&mut buf)?;
+    if bytes_read == 0 {
+      break;
+    }
+    hasher.update(&buf[..bytes_read]);
+  }
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn verify_sha256_checksum_from_reader(reader: &mut impl Read, checksum: &str) -> Result<(), ErrBox> {
+  let reader_checksum = get_sha256_checksum_from_reader(reader)?;
+  if reader_checksum != checksum {
+    err!(
+      "The checksum {} did not match the expected checksum of {}.",
+      reader_checksum,
+      checksum
+    )
+  } else {
+    Ok(())
+  }
+}
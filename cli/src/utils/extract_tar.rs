@@ -1,53 +1,66 @@
 use dprint_cli_core::types::ErrBox;
+use flate2::read::GzDecoder;
 use std::io::prelude::*;
 use std::path::Path;
+use std::sync::Mutex;
 
 use crate::environment::Environment;
 use tar::Archive;
 
+/// Extracts the tar.gz archive as it's read from the stream.
 pub fn extract_tar_gz(
   message: &str,
   environment: &impl Environment,
-  tar_gz_bytes: &[u8],
+  reader: impl Read + Send,
+  total_size: u64,
   dir_path: &Path,
 ) -> Result<(), ErrBox> {
-  let tar_bytes = super::gz_decompress(&tar_gz_bytes)?;
-  extract_tar(message, environment, &tar_bytes, dir_path)
-}
-
-pub fn extract_tar(
-  message: &str,
-  environment: &impl Environment,
-  tar_bytes: &[u8],
-  dir_path: &Path,
-) -> Result<(), ErrBox> {
-  let length = tar_bytes.len();
-
+  // the mutex makes the reader sync for the progress action
+  let reader = Mutex::new(reader);
   environment.log_action_with_progress(
     message,
     move |update_size| -> Result<(), ErrBox> {
-      let reader = std::io::Cursor::new(tar_bytes);
-      let mut a = Archive::new(reader);
-      let mut position = 0;
-      for entry in a.entries()? {
-        let mut entry = entry?;
-        let file_path = dir_path.join(entry.path()?);
-        if environment.is_real() {
-          if cfg!(unix) {
-            entry.set_preserve_permissions(true);
-          }
-
-          entry.unpack_in(&dir_path)?;
-        } else {
-          let mut bytes = Vec::new();
-          entry.read_to_end(&mut bytes)?;
-          environment.write_file(&file_path, &bytes)?;
-        }
-        position += entry.size();
-        update_size(position as usize);
-      }
-      Ok(())
+      let reader = ProgressReader {
+        inner: reader.into_inner().unwrap(),
+        position: 0,
+        update_size,
+      };
+      extract_tar(environment, GzDecoder::new(reader), dir_path)
     },
-    length,
+    total_size as usize,
   )
 }
+
+fn extract_tar(environment: &impl Environment, reader: impl Read, dir_path: &Path) -> Result<(), ErrBox> {
+  let mut a = Archive::new(reader);
+  for entry in a.entries()? {
+    let mut entry = entry?;
+    if environment.is_real() {
+      if cfg!(unix) {
+        entry.set_preserve_permissions(true);
+      }
+
+      entry.unpack_in(&dir_path)?;
+    } else {
+      let file_path = dir_path.join(entry.path()?);
+      environment.write_file_from_reader(&file_path, &mut entry)?;
+    }
+  }
+  Ok(())
+}
+
+/// Reports how many of the compressed bytes have been read.
+struct ProgressReader<TRead: Read> {
+  inner: TRead,
+  position: usize,
+  update_size: Box<dyn Fn(usize)>,
+}
+
+impl<TRead: Read> Read for ProgressReader<TRead> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let bytes_read = self.inner.read(buf)?;
+    self.position += bytes_read;
+    (self.update_size)(self.position);
+    Ok(bytes_read)
+  }
+}
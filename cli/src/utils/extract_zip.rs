@@ -1,25 +1,30 @@
 use dprint_cli_core::types::ErrBox;
 use std::io::prelude::*;
 use std::path::Path;
+use std::sync::Mutex;
 
 use crate::environment::Environment;
 
 // todo: consolidate with code in dprint
 
+/// Extracts the zip archive from the stream, which must be seekable
+/// because a zip file's directory is at its end.
 pub fn extract_zip(
   message: &str,
   environment: &impl Environment,
-  zip_bytes: &[u8],
+  reader: impl Read + Seek + Send,
   dir_path: &Path,
 ) -> Result<(), ErrBox> {
   // adapted from https://github.com/mvdnes/zip-rs/blob/master/examples/extract.rs
-  let reader = std::io::Cursor::new(&zip_bytes);
-  let mut zip = zip::ZipArchive::new(reader)?;
+  let zip = zip::ZipArchive::new(reader)?;
   let length = zip.len();
+  // the mutex makes the archive sync for the progress action
+  let zip = Mutex::new(zip);
 
   environment.log_action_with_progress(
     message,
     move |update_size| -> Result<(), ErrBox> {
+      let mut zip = zip.into_inner().unwrap();
       // todo: consider parallelizing this
       for i in 0..zip.len() {
         update_size(i);
@@ -28,9 +33,7 @@ pub fn extract_zip(
           let file_path = dir_path.join(&file_name);
 
           if !file.is_dir() {
-            let mut file_bytes = Vec::with_capacity(file.size() as usize);
-            file.read_to_end(&mut file_bytes)?;
-            environment.write_file(&file_path, &file_bytes)?;
+            environment.write_file_from_reader(&file_path, &mut file)?;
           } else {
             environment.create_dir_all(&file_path)?
           }
@@ -0,0 +1,49 @@
+use dprint_cli_core::types::ErrBox;
+use flate2::read::GzDecoder;
+use std::io::{Read, Seek};
+use tar::Archive;
+
+pub struct ArchiveFile {
+  /// Path of the file within the archive using forward slashes.
+  pub path: String,
+  pub unix_mode: Option<u32>,
+}
+
+impl ArchiveFile {
+  /// Gets if the file has an executable bit set. Returns true when the archive doesn't store permissions.
+  pub fn is_executable(&self) -> bool {
+    self.unix_mode.map(|mode| mode & 0o111 != 0).unwrap_or(true)
+  }
+}
+
+/// Gets the files in the zip archive.
+pub fn list_zip_files(reader: impl Read + Seek) -> Result<Vec<ArchiveFile>, ErrBox> {
+  let mut zip = zip::ZipArchive::new(reader)?;
+  let mut files = Vec::new();
+  for i in 0..zip.len() {
+    let file = zip.by_index(i)?;
+    if !file.is_dir() && file.enclosed_name().is_some() {
+      files.push(ArchiveFile {
+        path: file.name().replace('\\', "/"),
+        unix_mode: file.unix_mode(),
+      });
+    }
+  }
+  Ok(files)
+}
+
+/// Gets the files in the tar.gz archive.
+pub fn list_tar_gz_files(reader: impl Read) -> Result<Vec<ArchiveFile>, ErrBox> {
+  let mut archive = Archive::new(GzDecoder::new(reader));
+  let mut files = Vec::new();
+  for entry in archive.entries()? {
+    let entry = entry?;
+    if entry.header().entry_type().is_file() {
+      files.push(ArchiveFile {
+        path: entry.path()?.to_string_lossy().replace('\\', "/"),
+        unix_mode: entry.header().mode().ok(),
+      });
+    }
+  }
+  Ok(files)
+}
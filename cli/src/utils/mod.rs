@@ -1,15 +1,17 @@
+mod checksums;
 mod extract_tar;
 mod extract_zip;
 mod get_path_executable_path;
 mod get_shim_dir;
-mod gz_decompress;
+mod list_archive_files;
 mod string_utils;
 mod url;
 
 pub use self::url::*;
+pub use checksums::*;
 pub use extract_tar::*;
 pub use extract_zip::*;
 pub use get_path_executable_path::*;
 pub use get_shim_dir::*;
-pub use gz_decompress::*;
+pub use list_archive_files::*;
 pub use string_utils::*;
@@ -21,3 +21,28 @@ pub fn sentence_join(items: &Vec<String>) -> String {
     }
   }
 }
+
+/// Quotes the text as a single argument for sh.
+pub fn quote_sh(text: &str) -> String {
+  format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Quotes the text as a single argument for fish.
+pub fn quote_fish(text: &str) -> String {
+  format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Quotes the text as a single argument for powershell.
+pub fn quote_powershell(text: &str) -> String {
+  // powershell also treats the typographic single quotes as quotes
+  let mut result = String::with_capacity(text.len() + 2);
+  result.push('\'');
+  for c in text.chars() {
+    if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+      result.push(c);
+    }
+    result.push(c);
+  }
+  result.push('\'');
+  result
+}
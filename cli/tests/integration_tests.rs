@@ -110,6 +110,42 @@ fn sh_integration() {
   );
 }
 
+#[cfg(unix)]
+#[test]
+fn fish_integration() {
+  if Command::new("fish").arg("--version").output().is_err() {
+    println!("Skipping because fish is not installed.");
+    return;
+  }
+  ensure_setup();
+  let cli_folder = get_cli_folder();
+  let envs = get_env_vars();
+  let cwd = cli_folder.join("tests/specs");
+
+  let result = Command::new("fish")
+    .arg(cli_folder.join("tests/specs/tests.fish"))
+    .current_dir(cwd)
+    .envs(envs)
+    .arg(get_root_folder())
+    .output()
+    .unwrap();
+  let stdout = strip_ansi_escapes::strip(&result.stdout).unwrap();
+  let output = String::from_utf8_lossy(&stdout);
+  let error = String::from_utf8_lossy(&result.stderr);
+
+  println!("Output: {}", output);
+  println!("Error: {}", error);
+  println!("Status: {}", result.status);
+
+  assert_eq!(
+    output.trim().replace("\r\n", "\n"),
+    fs::read_to_string(cli_folder.join("tests/specs/tests.fish.out"))
+      .unwrap()
+      .trim()
+      .replace("\r\n", "\n")
+  );
+}
+
 fn ensure_setup() {
   INIT.call_once(|| {
     let paths = get_test_paths();
@@ -133,6 +169,8 @@ fn ensure_setup() {
       fs::copy("bvm.ps1", paths.bin_dir.join("bvm.ps1")).unwrap();
     } else {
       fs::copy("bvm-functions.sh", paths.bin_dir.join("bvm-functions")).unwrap();
+      fs::copy("bvm-functions.fish", paths.bin_dir.join("bvm-functions.fish")).unwrap();
+      fs::copy("bvm-init.fish", paths.bin_dir.join("bvm-init.fish")).unwrap();
     }
     let windows_bin = paths.build_folder.join("bvm-bin.exe");
     let unix_bin = paths.build_folder.join("bvm-bin");
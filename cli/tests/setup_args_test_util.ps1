@@ -18,7 +18,12 @@ $file_text=@'
     "commands": [{{
       "name": "args_test_util",
       "path": "args_test_util.exe"
-    }}]
+    }}],
+    "environment": {{
+      "variables": {{
+        "ARGS_TEST_UTIL_VAR": "a ^b & c | d <e> (f)"
+      }}
+    }}
   }}
 }}
 '@ -f $file_hash.ToLower()
@@ -21,7 +21,12 @@ file_text=$(cat << EOF
     "commands": [{
       "name": "args_test_util",
       "path": "args_test_util"
-    }]
+    }],
+    "environment": {
+      "variables": {
+        "ARGS_TEST_UTIL_VAR": "it's a \"value\" \$HOME"
+      }
+    }
   },
   "linux-x86_64": {
     "path": "./args_test_util.zip",
@@ -30,7 +35,12 @@ file_text=$(cat << EOF
     "commands": [{
       "name": "args_test_util",
       "path": "args_test_util"
-    }]
+    }],
+    "environment": {
+      "variables": {
+        "ARGS_TEST_UTIL_VAR": "it's a \"value\" \$HOME"
+      }
+    }
   }
 }
 EOF
@@ -1,6 +1,8 @@
 @echo off
 
 CALL bvm install --use ../../../temp/args_test_util.json
+REM the binary's environment variables should be set as is in the current session
+SET ARGS_TEST_UTIL_VAR
 CALL args_test_util "console.log(\"hello\")"
 CALL args_test_util "console.log(2 != 3)"
 REM cmd supports no quotes here, but powershell and cmd both require them
@@ -0,0 +1,16 @@
+#!/usr/bin/env fish
+
+# setup and source the bvm function
+set root_dir $argv[1]
+set -gx BVM_INSTALL_DIR $root_dir/temp/home_dir/bvm
+source $BVM_INSTALL_DIR/bin/bvm-init.fish; or exit $status
+
+bvm install --use $root_dir/temp/args_test_util.json; or exit $status
+args_test_util "console.log(\"hello\")"
+args_test_util "console.log(2 != 3)"
+args_test_util "JSON.stringify({})"
+args_test_util lib=""
+args_test_util lib=test,other
+bvm exec args_test_util 0.1.0 args_test_util "exec with spaces"
+# the binary's environment variables should be set in the current session
+echo "ARGS_TEST_UTIL_VAR=$ARGS_TEST_UTIL_VAR"
@@ -12,4 +12,9 @@ args_test_util "console.log(\"hello\")"
 args_test_util "console.log(2 != 3)"
 args_test_util "JSON.stringify({})"
 args_test_util lib=""
-args_test_util lib=test,other
\ No newline at end of file
+args_test_util lib=test,other
+bvm exec args_test_util 0.1.0 args_test_util "exec with spaces"
+# the binary's environment variables should be set in the current session
+echo "ARGS_TEST_UTIL_VAR=$ARGS_TEST_UTIL_VAR"
+# registry files can be created in a directory that doesn't exist yet
+(cd $root_dir/temp && bvm registry create new-registry > /dev/null && cat new-registry/registry.json)
@@ -59,7 +59,8 @@ if (Get-Command Expand-Archive -ErrorAction SilentlyContinue) {
 
 Remove-Item $BvmZip
 
-Start-Process -FilePath "$BinDir\bvm-bin" -ArgumentList "hidden","windows-install"
+# adds bvm to the path and recreates the shims, which are links to the previous bvm-bin
+Start-Process -FilePath "$BinDir\bvm-bin" -ArgumentList "hidden","windows-install" -Wait -NoNewWindow
 
 $Env:Path = "$env:APPDATA\bvm\shims;" + $Env:Path
 $Env:Path = "$BinDir;" + $Env:Path
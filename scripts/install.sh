@@ -49,6 +49,7 @@ rm "$exe.zip"
 
 if [ "$target" = "$windows_target" ]
 then
+  # adds bvm to the path and recreates the shims, which are links to the previous bvm-bin
   "$exe-bin.exe" hidden windows-install
 
   PATH="$APPDATA/bvm/shims:$bin_dir:$PATH"
@@ -61,18 +62,30 @@ else
   chmod +x "$exe-bin"
   chmod +x "$exe-init"
 
-  "$exe-bin" hidden unix-install
+  # the shims are hard links to (or copies of) bvm-bin, so recreate them to use the new executable
+  "$exe-bin" recreate-shims
 
   . $exe-init
   echo "bvm was installed successfully to $bin_dir"
   case $SHELL in
-  /bin/zsh) shell_profile=".zshrc" ;;
-  *) shell_profile=".bash_profile" ;;
+  */fish)
+    echo "Manually add the following to your \$HOME/.config/fish/config.fish if it's not already there:"
+    echo ""
+    echo "set -gx BVM_INSTALL_DIR \"$bvm_install\""
+    echo "source \"\$BVM_INSTALL_DIR/bin/bvm-init.fish\""
+    echo ""
+    ;;
+  *)
+    case $SHELL in
+    /bin/zsh) shell_profile=".zshrc" ;;
+    *) shell_profile=".bash_profile" ;;
+    esac
+    echo "Manually add the following to your \$HOME/$shell_profile (or similar) if it's not already there:"
+    echo ""
+    echo "export BVM_INSTALL_DIR=\"$bvm_install\""
+    echo ". \"\$BVM_INSTALL_DIR/bin/bvm-init\""
+    echo ""
+    ;;
   esac
-  echo "Manually add the following to your \$HOME/$shell_profile (or similar) if it's not already there:"
-  echo ""
-  echo "export BVM_INSTALL_DIR=\"$bvm_install\""
-  echo ". \"\$BVM_INSTALL_DIR/bin/bvm-init\""
-  echo ""
   echo "After, restart your shell and run 'bvm --help' to get started"
 fi